@@ -0,0 +1,104 @@
+//! An audit of a Tidy run says, for each inputted line, whether its word made
+//! it onto the new list, and if not, which stage removed it and why. Handy
+//! when a new list is shorter than expected.
+
+use crate::error::TidyError;
+use crate::file_writer::write_atomically;
+use crate::pipeline::STAGE_NAMES;
+use std::path::Path;
+
+/// Names `tidy_list` gives to the words it removes before running the stages...
+pub const STAGES_BEFORE_PIPELINE: [&str; 3] = ["take-first", "take-rand", "blank"];
+/// ...and after running them
+pub const STAGES_AFTER_PIPELINE: [&str; 3] = ["duplicate", "print-first", "print-rand"];
+
+/// What happened to one inputted line
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// The line as it was inputted
+    pub line: String,
+    /// The word from this line, as it was when it was removed (or kept)
+    pub word: String,
+    /// Name of the stage that removed this word, or `None` if it was kept
+    pub removed_by: Option<String>,
+    /// Why the word was removed, like "prefix of newspaper", if we know
+    pub reason: Option<String>,
+}
+
+/// Write the audit records to a CSV file, one row per inputted line. Like the
+/// new list, the file is written to a temporary file first, then renamed into place.
+pub fn write_audit(records: &[AuditRecord], path: &Path) -> Result<(), TidyError> {
+    write_atomically(path, |f| {
+        let mut writer = csv::Writer::from_writer(f);
+        writer.write_record(["line", "word", "removed_by", "reason"])?;
+        for record in records {
+            writer.write_record([
+                record.line.as_str(),
+                record.word.as_str(),
+                record.removed_by.as_deref().unwrap_or_default(),
+                record.reason.as_deref().unwrap_or_default(),
+            ])?;
+        }
+        writer.flush()
+    })
+}
+
+/// Count how many words each stage removed, in the order the stages usually run.
+/// Words that were kept are counted under "kept", last.
+/// ```
+/// use tidy::audit::{count_by_stage, AuditRecord};
+/// let record = |removed_by: Option<&str>| AuditRecord {
+///     line: "word".to_string(),
+///     word: "word".to_string(),
+///     removed_by: removed_by.map(|stage| stage.to_string()),
+///     reason: None,
+/// };
+/// let records = vec![record(Some("reject")), record(None), record(Some("lowercase")), record(Some("reject"))];
+/// assert_eq!(
+///     count_by_stage(&records),
+///     vec![("lowercase".to_string(), 1), ("reject".to_string(), 2), ("kept".to_string(), 1)]
+/// );
+/// ```
+pub fn count_by_stage(records: &[AuditRecord]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = vec![];
+    for stage in records
+        .iter()
+        .filter_map(|record| record.removed_by.as_deref())
+    {
+        match counts.iter_mut().find(|(name, _count)| name == stage) {
+            Some((_name, count)) => *count += 1,
+            None => counts.push((stage.to_string(), 1)),
+        }
+    }
+    // Stages that aren't Tidy's own (like a library user's) go last
+    let stage_position = |stage: &str| {
+        STAGES_BEFORE_PIPELINE
+            .iter()
+            .chain(STAGE_NAMES.iter())
+            .chain(STAGES_AFTER_PIPELINE.iter())
+            .position(|name| *name == stage)
+            .unwrap_or(usize::MAX)
+    };
+    // sort_by_key is stable, so other stages keep their order
+    counts.sort_by_key(|(stage, _count)| stage_position(stage));
+    let kept = records
+        .iter()
+        .filter(|record| record.removed_by.is_none())
+        .count();
+    counts.push(("kept".to_string(), kept));
+    counts
+}
+
+/// Print how many words each stage removed. We just want to "display" this
+/// information, so we use `eprintln!`
+pub fn display_audit_summary(records: &[AuditRecord]) {
+    eprintln!("Audit of {} inputted lines", records.len());
+    eprintln!("--------------------------");
+    for (stage, count) in count_by_stage(records) {
+        if stage == "kept" {
+            eprintln!("{:<28}: {}", stage, count);
+        } else {
+            eprintln!("{:<28}: {} removed", stage, count);
+        }
+    }
+}
@@ -1,61 +1,308 @@
-use radix_fmt::*; // https://stackoverflow.com/a/50278316
-
-/// Convert a number in a "card code" (base 26)
-pub fn print_as_cards(n: usize, list_length: usize) -> String {
-    let n_as_base_26 = radix(n, 26);
-
-    // Pad card codes with zeros
-    let n_width = n_as_base_26.to_string().len();
-    let pad_width = radix(list_length - 1, 26).to_string().len();
-
-    let mut padded_n = String::new();
-    for _i in n_width..pad_width {
-        padded_n.push('0');
-    }
-    // Now that we have the appropriate number of zeros
-    // in `padded_n`, it's time to add our number
-    padded_n += &n_as_base_26.to_string();
-
-    padded_n
-        .to_string()
-        .chars()
-        .map(|ch| char_to_card(ch) + "-")
-        .collect::<String>()
-        .trim_end_matches('-')
-        .trim()
-        .to_string()
-}
-
-/// Convert 0-z inputted character to a 3-character "card code"
-fn char_to_card(ch: char) -> String {
-    match ch {
-        '0' => "B02",
-        '1' => "B03",
-        '2' => "B04",
-        '3' => "B05",
-        '4' => "B06",
-        '5' => "B07",
-        '6' => "B08",
-        '7' => "B09",
-        '8' => "B10",
-        '9' => "BJa",
-        'a' => "BQu",
-        'b' => "BKi",
-        'c' => "BAc",
-        'd' => "R02",
-        'e' => "R03",
-        'f' => "R04",
-        'g' => "R05",
-        'h' => "R06",
-        'i' => "R07",
-        'j' => "R08",
-        'k' => "R09",
-        'l' => "R10",
-        'm' => "RJa",
-        'n' => "RQu",
-        'o' => "RKi",
-        'p' => "RAc",
-        _ => panic!("Unable to convert this number from a letter to a card code."),
-    }
-    .to_string()
+use crate::error::TidyError;
+
+/// The 13 ranks of a suit, as the last two characters of a card code
+const RANKS: [&str; 13] = [
+    "02", "03", "04", "05", "06", "07", "08", "09", "10", "Ja", "Qu", "Ki", "Ac",
+];
+
+/// Which cards to use when printing card codes
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum CardDeck {
+    /// Only tell black cards from red ones, so 26 different cards (B02 to RAc)
+    #[default]
+    BlackAndRed,
+    /// Use all four suits (clubs, diamonds, hearts and spades) of this many decks.
+    /// Each deck has 52 cards. With more than one deck, each card code starts with
+    /// the deck number, so decks need to be told apart, like by the color of their backs.
+    FullDecks(u8),
+}
+
+impl CardDeck {
+    /// The number of different cards, which is the base of the card codes
+    pub fn number_of_cards(&self) -> usize {
+        match self {
+            CardDeck::BlackAndRed => 26,
+            // We treat zero decks as one deck
+            CardDeck::FullDecks(decks) => 52 * (*decks).max(1) as usize,
+        }
+    }
+
+    /// The code of the nth card, like "B02" or, with multiple decks, "2HQu"
+    fn card(&self, n: usize) -> String {
+        match self {
+            CardDeck::BlackAndRed => format!("{}{}", ["B", "R"][n / 13], RANKS[n % 13]),
+            CardDeck::FullDecks(decks) => {
+                let suit = ["C", "D", "H", "S"][(n % 52) / 13];
+                if *decks > 1 {
+                    format!("{}{}{}", n / 52 + 1, suit, RANKS[n % 13])
+                } else {
+                    format!("{}{}", suit, RANKS[n % 13])
+                }
+            }
+        }
+    }
+
+    /// Find which card a card code is. Inverse of `card`.
+    fn find_card(&self, code: &str) -> Option<usize> {
+        (0..self.number_of_cards()).find(|n| self.card(*n) == code)
+    }
+}
+
+/// Convert a number into a "card code", a sequence of cards joined by dashes,
+/// padded so that every code for a list of this length has the same number of cards.
+/// Note that `n` should be zero-indexed.
+/// ```
+/// use tidy::cards::{print_as_cards, CardDeck};
+/// assert_eq!(print_as_cards(27, 26 * 26, CardDeck::BlackAndRed), "B03-B03");
+/// assert_eq!(print_as_cards(2703, 52 * 52, CardDeck::FullDecks(1)), "SAc-SAc");
+/// assert_eq!(print_as_cards(53, 104, CardDeck::FullDecks(2)), "2C03");
+/// ```
+pub fn print_as_cards(n: usize, list_length: usize, deck: CardDeck) -> String {
+    let base = deck.number_of_cards();
+    let mut cards = vec![];
+    let mut remaining = n;
+    for _i in 0..cards_needed_with_replacement(list_length, deck) {
+        cards.push(deck.card(remaining % base));
+        remaining /= base;
+    }
+    cards.reverse();
+    cards.join("-")
+}
+
+/// Find how many cards need to be drawn, with replacement, to give every word of
+/// a list its own card code
+/// ```
+/// use tidy::cards::{cards_needed_with_replacement, CardDeck};
+/// assert_eq!(cards_needed_with_replacement(26 * 26, CardDeck::BlackAndRed), 2);
+/// assert_eq!(cards_needed_with_replacement(26 * 26 + 1, CardDeck::BlackAndRed), 3);
+/// ```
+pub fn cards_needed_with_replacement(list_length: usize, deck: CardDeck) -> usize {
+    let base = deck.number_of_cards();
+    // Find how many cards we need for the last word of the list
+    let mut cards_needed = 1;
+    let mut largest = list_length.saturating_sub(1) / base;
+    while largest > 0 {
+        cards_needed += 1;
+        largest /= base;
+    }
+    cards_needed
+}
+
+/// Convert a card code back into the (zero-indexed) number of its word on the list.
+/// Returns an error if the code has a card that isn't in the deck.
+/// ```
+/// use tidy::cards::{decode_cards, print_as_cards, CardDeck};
+/// let deck = CardDeck::FullDecks(1);
+/// assert_eq!(decode_cards("SAc-SAc", deck).unwrap(), 2703);
+/// assert_eq!(decode_cards(&print_as_cards(1000, 2704, deck), deck).unwrap(), 1000);
+/// assert!(decode_cards("B02", deck).is_err());
+/// ```
+pub fn decode_cards(code: &str, deck: CardDeck) -> Result<usize, TidyError> {
+    code.trim()
+        .split('-')
+        .try_fold(0_usize, |n, card| match deck.find_card(card) {
+            Some(card_number) => n
+                .checked_mul(deck.number_of_cards())
+                .and_then(|n| n.checked_add(card_number))
+                .ok_or_else(|| {
+                    TidyError::invalid_option("card code", code, "too many cards to decode")
+                }),
+            None => Err(TidyError::invalid_option(
+                "card code",
+                code,
+                &format!("{:?} isn't a card in this deck", card),
+            )),
+        })
+}
+
+/// Options for printing card codes
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct CardOptions {
+    pub deck: CardDeck,
+    /// Whether cards are drawn without putting them back (like from a shuffled deck),
+    /// so a card can only appear once in each card code
+    pub without_replacement: bool,
+}
+
+/// Print the card code of the nth word of a list, drawing cards with or without
+/// replacement
+pub fn print_card_code(n: usize, list_length: usize, options: CardOptions) -> String {
+    if options.without_replacement {
+        print_as_drawn_cards(n, list_length, options.deck)
+    } else {
+        print_as_cards(n, list_length, options.deck)
+    }
+}
+
+/// Convert a card code back into the (zero-indexed) number of its word on the list,
+/// drawing cards with or without replacement. Inverse of `print_card_code`.
+pub fn decode_card_code(code: &str, options: CardOptions) -> Result<usize, TidyError> {
+    if options.without_replacement {
+        decode_drawn_cards(code, options.deck)
+    } else {
+        decode_cards(code, options.deck)
+    }
+}
+
+/// Find how many cards need to be drawn, without replacement, to give every word
+/// of a list its own card code. Drawing k cards from a deck of n cards gives
+/// n!/(n-k)! different card codes.
+/// ```
+/// use tidy::cards::{cards_needed_without_replacement, CardDeck};
+/// // 52 * 51 = 2652 codes
+/// assert_eq!(cards_needed_without_replacement(2652, CardDeck::FullDecks(1)), 2);
+/// assert_eq!(cards_needed_without_replacement(2653, CardDeck::FullDecks(1)), 3);
+/// ```
+pub fn cards_needed_without_replacement(list_length: usize, deck: CardDeck) -> usize {
+    let number_of_cards = deck.number_of_cards();
+    let mut cards_needed = 1;
+    while cards_needed < number_of_cards
+        && count_card_codes_without_replacement(cards_needed, deck)
+            .is_some_and(|codes| codes < list_length)
+    {
+        cards_needed += 1;
+    }
+    cards_needed
+}
+
+/// Count the different card codes made by drawing this many cards without
+/// replacement: n!/(n-k)!. Returns `None` if there are too many to count.
+pub fn count_card_codes_without_replacement(cards_drawn: usize, deck: CardDeck) -> Option<usize> {
+    let number_of_cards = deck.number_of_cards();
+    (0..cards_drawn).try_fold(1_usize, |codes, i| {
+        codes.checked_mul(number_of_cards.checked_sub(i)?)
+    })
+}
+
+/// Calculate the entropy, in bits, of drawing this many cards without replacement
+/// from a shuffled deck: log2(n!/(n-k)!).
+/// ```
+/// use tidy::cards::{calc_entropy_of_drawn_cards, CardDeck};
+/// assert_eq!(calc_entropy_of_drawn_cards(2, CardDeck::FullDecks(1)), 2652_f64.log2());
+/// ```
+pub fn calc_entropy_of_drawn_cards(cards_drawn: usize, deck: CardDeck) -> f64 {
+    let number_of_cards = deck.number_of_cards();
+    (0..cards_drawn.min(number_of_cards))
+        .map(|i| ((number_of_cards - i) as f64).log2())
+        .sum()
+}
+
+/// Convert a number into a card code of cards drawn without replacement, so no card
+/// appears twice in a code. Every code for a list of this length has the same number
+/// of cards. Note that `n` should be zero-indexed.
+///
+/// The first card can be any of the deck's n cards, the second any of the n - 1 cards
+/// left, and so on, so we write `n` as a mixed-radix number with those bases, then use
+/// each digit to pick from the cards that haven't been drawn yet, in deck order.
+/// ```
+/// use tidy::cards::{print_as_drawn_cards, CardDeck};
+/// let deck = CardDeck::FullDecks(1);
+/// assert_eq!(print_as_drawn_cards(0, 2652, deck), "C02-C03");
+/// assert_eq!(print_as_drawn_cards(51, 2652, deck), "C03-C02");
+/// assert_eq!(print_as_drawn_cards(2651, 2652, deck), "SAc-SKi");
+/// ```
+pub fn print_as_drawn_cards(n: usize, list_length: usize, deck: CardDeck) -> String {
+    let cards_drawn = cards_needed_without_replacement(list_length, deck);
+    let number_of_cards = deck.number_of_cards();
+    // Find the digits, least significant (the last card drawn) first
+    let mut digits = vec![];
+    let mut remaining = n;
+    for i in (0..cards_drawn).rev() {
+        let base = number_of_cards - i;
+        digits.push(remaining % base);
+        remaining /= base;
+    }
+    digits.reverse();
+
+    let mut cards_left: Vec<usize> = (0..number_of_cards).collect();
+    digits
+        .into_iter()
+        .map(|digit| deck.card(cards_left.remove(digit)))
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+/// Convert a card code of cards drawn without replacement back into the
+/// (zero-indexed) number of its word on the list. Inverse of `print_as_drawn_cards`.
+/// Returns an error if a card isn't in the deck or is drawn twice.
+/// ```
+/// use tidy::cards::{decode_drawn_cards, CardDeck};
+/// let deck = CardDeck::FullDecks(1);
+/// assert_eq!(decode_drawn_cards("SAc-SKi", deck).unwrap(), 2651);
+/// assert!(decode_drawn_cards("SAc-SAc", deck).is_err());
+/// ```
+pub fn decode_drawn_cards(code: &str, deck: CardDeck) -> Result<usize, TidyError> {
+    let number_of_cards = deck.number_of_cards();
+    let mut cards_left: Vec<usize> = (0..number_of_cards).collect();
+    let mut n: usize = 0;
+    for card in code.trim().split('-') {
+        let card_number = deck.find_card(card).ok_or_else(|| {
+            TidyError::invalid_option(
+                "card code",
+                code,
+                &format!("{:?} isn't a card in this deck", card),
+            )
+        })?;
+        let digit = cards_left
+            .iter()
+            .position(|c| *c == card_number)
+            .ok_or_else(|| {
+                TidyError::invalid_option(
+                    "card code",
+                    code,
+                    &format!("{:?} is drawn more than once", card),
+                )
+            })?;
+        n = n
+            .checked_mul(cards_left.len())
+            .and_then(|n| n.checked_add(digit))
+            .ok_or_else(|| {
+                TidyError::invalid_option("card code", code, "too many cards to decode")
+            })?;
+        cards_left.remove(digit);
+    }
+    Ok(n)
+}
+
+/// Print how many cards are drawn for each word and the entropy of those draws.
+/// We just want to "display" this information, so we use `eprintln!`
+pub fn display_card_information(list_length: usize, options: CardOptions) {
+    let number_of_cards = options.deck.number_of_cards();
+    let (cards_drawn, number_of_codes, entropy) = if options.without_replacement {
+        let cards_drawn = cards_needed_without_replacement(list_length, options.deck);
+        (
+            cards_drawn,
+            count_card_codes_without_replacement(cards_drawn, options.deck),
+            calc_entropy_of_drawn_cards(cards_drawn, options.deck),
+        )
+    } else {
+        let cards_drawn = cards_needed_with_replacement(list_length, options.deck);
+        (
+            cards_drawn,
+            number_of_cards.checked_pow(cards_drawn as u32),
+            cards_drawn as f64 * (number_of_cards as f64).log2(),
+        )
+    };
+    let heading = if options.without_replacement {
+        "Card codes, drawn without replacement"
+    } else {
+        "Card codes, drawn with replacement"
+    };
+    eprintln!("{}", heading);
+    eprintln!("{}", "-".repeat(heading.len()));
+    eprintln!(
+        "Cards per word            : {} (from {} different cards)",
+        cards_drawn, number_of_cards
+    );
+    match number_of_codes {
+        Some(number_of_codes) => {
+            eprintln!("Possible card codes       : {}", number_of_codes);
+            eprintln!(
+                "Card codes used by list   : {:.2}%",
+                100.0 * list_length as f64 / number_of_codes as f64
+            );
+        }
+        None => eprintln!("Possible card codes       : more than {}", usize::MAX),
+    }
+    eprintln!("Entropy of drawn cards    : {:.3} bits", entropy);
 }
@@ -0,0 +1,145 @@
+//! Transparently read and write compressed word lists and corpora.
+//!
+//! Inputted files are detected by their "magic bytes" (the first few bytes
+//! of the file), rather than their file extension, so that compressed data
+//! piped in through stdin works too. Outputted files are compressed based on
+//! the extension of the given output path.
+
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressionFormat {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl CompressionFormat {
+    /// Check the first few bytes of a file for the magic numbers that
+    /// gzip, xz and zstd files start with.
+    /// ```
+    /// use tidy::compression::CompressionFormat;
+    /// assert_eq!(CompressionFormat::from_magic_bytes(&[0x1f, 0x8b, 0x08]), Some(CompressionFormat::Gzip));
+    /// assert_eq!(CompressionFormat::from_magic_bytes(b"abacus"), None);
+    /// ```
+    pub fn from_magic_bytes(bytes: &[u8]) -> Option<CompressionFormat> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(CompressionFormat::Gzip)
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(CompressionFormat::Xz)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(CompressionFormat::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Determine which compression format (if any) to use, given
+    /// an output path.
+    /// ```
+    /// use std::path::Path;
+    /// use tidy::compression::CompressionFormat;
+    /// assert_eq!(CompressionFormat::from_extension(Path::new("list.txt.zst")), Some(CompressionFormat::Zstd));
+    /// assert_eq!(CompressionFormat::from_extension(Path::new("list.txt")), None);
+    /// ```
+    pub fn from_extension(path: &Path) -> Option<CompressionFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "gz" => Some(CompressionFormat::Gzip),
+            "xz" => Some(CompressionFormat::Xz),
+            "zst" => Some(CompressionFormat::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// The longest magic number we check for is xz's, at 6 bytes.
+const MAGIC_BYTES_LENGTH: u64 = 6;
+
+/// Peek at the start of the given reader. If it looks like gzip, xz or zstd
+/// data, wrap the reader in the appropriate stream decompressor. If not,
+/// hand back a reader that still yields every byte, including the ones we
+/// peeked at.
+pub fn decompress_if_needed(mut reader: Box<dyn BufRead>) -> std::io::Result<Box<dyn BufRead>> {
+    let mut magic_bytes = vec![];
+    // Using take (rather than fill_buf) means we get all the bytes we
+    // need, even if stdin hands them to us in small pieces.
+    (&mut reader)
+        .take(MAGIC_BYTES_LENGTH)
+        .read_to_end(&mut magic_bytes)?;
+    let format = CompressionFormat::from_magic_bytes(&magic_bytes);
+    let reader = BufReader::new(Cursor::new(magic_bytes).chain(reader));
+    Ok(match format {
+        // Some gzipped corpora are made up of multiple gzip "members",
+        // so use the multi-member decoder
+        Some(CompressionFormat::Gzip) => {
+            Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader)))
+        }
+        Some(CompressionFormat::Xz) => Box::new(BufReader::new(
+            xz2::bufread::XzDecoder::new_multi_decoder(reader),
+        )),
+        Some(CompressionFormat::Zstd) => {
+            Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?))
+        }
+        None => Box::new(reader),
+    })
+}
+
+/// A writer that may compress what's written to it. Compressed streams end
+/// with a trailer, so `finish` must be called once everything is written.
+pub enum OutputWriter<W: Write> {
+    Uncompressed(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Xz(xz2::write::XzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> OutputWriter<W> {
+    /// Finish any compressed stream, returning the inner writer. Unlike
+    /// dropping the writer, this returns any error from writing the trailer.
+    pub fn finish(self) -> std::io::Result<W> {
+        match self {
+            OutputWriter::Uncompressed(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+            OutputWriter::Gzip(encoder) => encoder.finish(),
+            OutputWriter::Xz(encoder) => encoder.finish(),
+            OutputWriter::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for OutputWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputWriter::Uncompressed(writer) => writer.write(buf),
+            OutputWriter::Gzip(encoder) => encoder.write(buf),
+            OutputWriter::Xz(encoder) => encoder.write(buf),
+            OutputWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputWriter::Uncompressed(writer) => writer.flush(),
+            OutputWriter::Gzip(encoder) => encoder.flush(),
+            OutputWriter::Xz(encoder) => encoder.flush(),
+            OutputWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Wrap the given output file (or other writer) in a compressor, if the
+/// output path ends in ".gz", ".xz", or ".zst". Call `finish` on the
+/// returned writer once the whole list is written.
+pub fn compress_for_path<W: Write>(writer: W, path: &Path) -> std::io::Result<OutputWriter<W>> {
+    Ok(match CompressionFormat::from_extension(path) {
+        Some(CompressionFormat::Gzip) => OutputWriter::Gzip(flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        )),
+        Some(CompressionFormat::Xz) => OutputWriter::Xz(xz2::write::XzEncoder::new(writer, 6)),
+        Some(CompressionFormat::Zstd) => OutputWriter::Zstd(zstd::Encoder::new(writer, 0)?),
+        None => OutputWriter::Uncompressed(writer),
+    })
+}
@@ -0,0 +1,305 @@
+//! Decode the raw bytes of inputted files into lines of text, handling
+//! byte order marks (BOMs), legacy encodings and lines that aren't valid
+//! in the expected encoding.
+
+use encoding_rs::{Decoder, DecoderResult, Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io::{self, BufRead, Chain, Cursor, Read};
+
+/// Text encodings Tidy can read inputted files in
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum InputEncoding {
+    #[default]
+    Utf8,
+    Latin1,
+    Windows1252,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl fmt::Display for InputEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputEncoding::Utf8 => write!(f, "UTF-8"),
+            InputEncoding::Latin1 => write!(f, "Latin-1"),
+            InputEncoding::Windows1252 => write!(f, "Windows-1252"),
+            InputEncoding::Utf16Le => write!(f, "UTF-16LE"),
+            InputEncoding::Utf16Be => write!(f, "UTF-16BE"),
+        }
+    }
+}
+
+// Serialized as it'd be typed on the command line, for recipes
+impl Serialize for InputEncoding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse user's `--encoding` input. Case and dashes/underscores don't matter.
+/// ```
+/// use tidy::decoding::{parse_encoding, InputEncoding};
+/// assert_eq!(parse_encoding("UTF-8"), Ok(InputEncoding::Utf8));
+/// assert_eq!(parse_encoding("latin1"), Ok(InputEncoding::Latin1));
+/// assert_eq!(parse_encoding("cp1252"), Ok(InputEncoding::Windows1252));
+/// assert!(parse_encoding("ebcdic").is_err());
+/// ```
+pub fn parse_encoding(input: &str) -> Result<InputEncoding, String> {
+    match input.to_lowercase().replace(['-', '_'], "").as_str() {
+        "utf8" => Ok(InputEncoding::Utf8),
+        "latin1" | "iso88591" => Ok(InputEncoding::Latin1),
+        "windows1252" | "cp1252" => Ok(InputEncoding::Windows1252),
+        "utf16" | "utf16le" => Ok(InputEncoding::Utf16Le),
+        "utf16be" => Ok(InputEncoding::Utf16Be),
+        _ => Err(format!(
+            "Unknown encoding {}. Please use one of the following: utf-8, latin-1, windows-1252, utf-16le, or utf-16be.",
+            input
+        )),
+    }
+}
+
+/// Options for how Tidy reads the lines of inputted files
+#[derive(Default, Debug, Clone)]
+pub struct ReadOptions {
+    pub encoding: InputEncoding,
+    /// If true, replace invalid bytes with the Unicode replacement character (�)
+    /// rather than skipping the whole line.
+    pub lossy: bool,
+    /// If given, ignore lines that start with this prefix (like "#"), so that
+    /// lists can have comments in them.
+    pub comment_prefix: Option<String>,
+}
+
+/// Line numbers (1-indexed) of lines that weren't valid in the expected
+/// encoding, and so were either skipped or repaired.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct DecodingReport {
+    /// The encoding the file was actually decoded with, which may differ from
+    /// the requested encoding if the file started with a byte order mark.
+    pub encoding: InputEncoding,
+    pub skipped_lines: Vec<usize>,
+    pub repaired_lines: Vec<usize>,
+}
+
+/// Decode the bytes of an entire file into lines.
+///
+/// A byte order mark (BOM) at the start of the file overrides the given encoding
+/// (and is not included in the first line), so UTF-16 files with a BOM are detected
+/// automatically.
+pub fn decode_lines(bytes: &[u8], options: &ReadOptions) -> (Vec<String>, DecodingReport) {
+    // Reading from a slice of bytes can't fail
+    let (numbered_lines, report) = decode_numbered_lines(bytes, options)
+        .unwrap_or_else(|_| (vec![], DecodingReport::default()));
+    let lines = numbered_lines
+        .into_iter()
+        .map(|(_line_number, line)| line)
+        .collect();
+    (lines, report)
+}
+
+/// Decode the lines of a file as they're read, pairing each line with its line
+/// number (1-indexed). Skipped lines still count towards the line numbers of later
+/// lines. Like `decode_lines`, a byte order mark overrides the given encoding.
+pub fn decode_numbered_lines<R: BufRead>(
+    reader: R,
+    options: &ReadOptions,
+) -> io::Result<(Vec<(usize, String)>, DecodingReport)> {
+    let line_decoder = LineDecoder::new(reader, options)?;
+    let mut report = DecodingReport {
+        encoding: line_decoder.encoding,
+        ..Default::default()
+    };
+    let mut numbered_lines = vec![];
+    for (line_number, line) in (1..).zip(line_decoder) {
+        match line? {
+            DecodedLine::Valid(line) => numbered_lines.push((line_number, line)),
+            DecodedLine::Repaired(line) => {
+                report.repaired_lines.push(line_number);
+                numbered_lines.push((line_number, line));
+            }
+            DecodedLine::Skipped => report.skipped_lines.push(line_number),
+        }
+    }
+    Ok((numbered_lines, report))
+}
+
+/// One decoded line of an inputted file
+enum DecodedLine {
+    Valid(String),
+    /// Line had invalid bytes, which were replaced with the replacement character
+    Repaired(String),
+    /// Line had invalid bytes, and so was left out
+    Skipped,
+}
+
+/// Reads and decodes one line at a time, so that the whole file doesn't need to be
+/// held in memory as bytes.
+struct LineDecoder<R: BufRead> {
+    reader: R,
+    encoding: InputEncoding,
+    lossy: bool,
+    /// Only used for UTF-16, which can't be split on newline bytes
+    utf16: Option<Utf16Text>,
+}
+
+/// Text decoded from UTF-16 that hasn't been split into lines yet
+struct Utf16Text {
+    decoder: Decoder,
+    text: String,
+    /// Positions in `text` where the decoder found malformed input, and so
+    /// inserted a replacement character
+    error_offsets: Vec<usize>,
+    finished: bool,
+}
+
+impl<R: BufRead> LineDecoder<R> {
+    fn new(reader: R, options: &ReadOptions) -> io::Result<LineDecoder<Chain<Cursor<Vec<u8>>, R>>> {
+        // Read just enough to check for a byte order mark, then put back any
+        // bytes that aren't part of one
+        let mut reader = reader;
+        let mut start = vec![];
+        (&mut reader).take(3).read_to_end(&mut start)?;
+        let encoding = match Encoding::for_bom(&start) {
+            Some((encoding, bom_length)) => {
+                start.drain(..bom_length);
+                if encoding == UTF_16LE {
+                    InputEncoding::Utf16Le
+                } else if encoding == UTF_16BE {
+                    InputEncoding::Utf16Be
+                } else {
+                    InputEncoding::Utf8
+                }
+            }
+            None => options.encoding,
+        };
+        let utf16 = match encoding {
+            InputEncoding::Utf16Le => Some(UTF_16LE),
+            InputEncoding::Utf16Be => Some(UTF_16BE),
+            _ => None,
+        }
+        .map(|utf16| Utf16Text {
+            decoder: utf16.new_decoder_without_bom_handling(),
+            text: String::new(),
+            error_offsets: vec![],
+            finished: false,
+        });
+        Ok(LineDecoder {
+            reader: Cursor::new(start).chain(reader),
+            encoding,
+            lossy: options.lossy,
+            utf16,
+        })
+    }
+}
+
+impl<R: BufRead> LineDecoder<R> {
+    /// Read one line of bytes and decode it, for encodings where we can split
+    /// on newline bytes
+    fn next_byte_line(&mut self) -> Option<io::Result<DecodedLine>> {
+        let mut line = vec![];
+        match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        let line = line.strip_suffix(b"\n").unwrap_or(&line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let decoded_line = match self.encoding {
+            InputEncoding::Utf8 => match std::str::from_utf8(line) {
+                Ok(line) => DecodedLine::Valid(line.to_string()),
+                Err(_) if self.lossy => {
+                    DecodedLine::Repaired(String::from_utf8_lossy(line).to_string())
+                }
+                Err(_) => DecodedLine::Skipped,
+            },
+            // Every byte is a valid Latin-1 (ISO-8859-1) character, and maps directly to
+            // the Unicode code point of the same number. (Note that encoding_rs treats
+            // "latin1" as Windows-1252, following the WHATWG spec, so we don't use it here.)
+            InputEncoding::Latin1 => DecodedLine::Valid(line.iter().map(|&b| b as char).collect()),
+            // Windows-1252 can also decode any byte, so there are no errors to report
+            _ => DecodedLine::Valid(WINDOWS_1252.decode_without_bom_handling(line).0.to_string()),
+        };
+        Some(Ok(decoded_line))
+    }
+
+    /// Decode UTF-16 until we have a whole line. Only lines where the decoder
+    /// itself found malformed input count as repaired or skipped, so a file can
+    /// contain the replacement character on purpose.
+    fn next_utf16_line(&mut self) -> Option<io::Result<DecodedLine>> {
+        let utf16 = self.utf16.as_mut()?;
+        loop {
+            let line_length = match utf16.text.find('\n') {
+                Some(newline) => Some(newline),
+                None if utf16.finished && !utf16.text.is_empty() => Some(utf16.text.len()),
+                None if utf16.finished => return None,
+                None => None,
+            };
+            if let Some(line_length) = line_length {
+                let had_errors = utf16
+                    .error_offsets
+                    .iter()
+                    .any(|&offset| offset < line_length);
+                let rest = utf16
+                    .text
+                    .split_off((line_length + 1).min(utf16.text.len()));
+                let mut line = std::mem::replace(&mut utf16.text, rest);
+                line.truncate(line_length);
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+                utf16.error_offsets = utf16
+                    .error_offsets
+                    .iter()
+                    .filter(|&&offset| offset > line_length)
+                    .map(|&offset| offset - line_length - 1)
+                    .collect();
+                return Some(Ok(match (had_errors, self.lossy) {
+                    (false, _) => DecodedLine::Valid(line),
+                    (true, true) => DecodedLine::Repaired(line),
+                    (true, false) => DecodedLine::Skipped,
+                }));
+            }
+            let mut bytes = match self.reader.fill_buf() {
+                Ok(bytes) => bytes,
+                Err(e) => return Some(Err(e)),
+            };
+            let bytes_read = bytes.len();
+            let last = bytes.is_empty();
+            loop {
+                let needed = utf16
+                    .decoder
+                    .max_utf8_buffer_length_without_replacement(bytes.len())
+                    .unwrap_or(bytes.len() * 3);
+                utf16.text.reserve(needed + 4);
+                let (result, read) = utf16.decoder.decode_to_string_without_replacement(
+                    bytes,
+                    &mut utf16.text,
+                    last,
+                );
+                bytes = &bytes[read..];
+                match result {
+                    DecoderResult::InputEmpty => break,
+                    DecoderResult::OutputFull => {}
+                    DecoderResult::Malformed(_, _) => {
+                        utf16.error_offsets.push(utf16.text.len());
+                        utf16.text.push('\u{FFFD}');
+                    }
+                }
+            }
+            self.reader.consume(bytes_read);
+            utf16.finished = last;
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LineDecoder<R> {
+    type Item = io::Result<DecodedLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.encoding {
+            InputEncoding::Utf16Le | InputEncoding::Utf16Be => self.next_utf16_line(),
+            _ => self.next_byte_line(),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::TidyError;
 use radix_fmt::*; // https://stackoverflow.com/a/50278316
 /// Print dice rolls before each corresponding word. Note
 /// that the `n` parameter should be zero-indexed. A tab (`\t`)
@@ -31,7 +32,22 @@ use radix_fmt::*; // https://stackoverflow.com/a/50278316
 ///
 /// I wish I could replicate this radix function easily without the dependency,
 /// but that doesn't seem [very easy](https://stackoverflow.com/a/50278316).
-pub fn print_as_dice(n: usize, base: u8, list_length: usize, use_letters: bool) -> String {
+///
+/// Returns an error if `base` isn't between 2 and 36.
+pub fn print_as_dice(
+    n: usize,
+    base: u8,
+    list_length: usize,
+    use_letters: bool,
+) -> Result<String, TidyError> {
+    // The radix function only handles bases from 2 to 36
+    if !(2..=36).contains(&base) {
+        return Err(TidyError::invalid_option(
+            "--dice",
+            &base.to_string(),
+            "dice must have between 2 and 36 sides",
+        ));
+    }
     // Set width for zero-padding
 
     // First, get the literal width of the largest number we'll be printing.
@@ -42,7 +58,7 @@ pub fn print_as_dice(n: usize, base: u8, list_length: usize, use_letters: bool)
 
     // Pad dice roll numbers with zeros
     let n_width = n_as_base.to_string().len();
-    let pad_width = radix(list_length - 1, base).to_string().len();
+    let pad_width = radix(list_length.saturating_sub(1), base).to_string().len();
 
     let mut padded_n = String::new();
     for _i in n_width..pad_width {
@@ -57,79 +73,90 @@ pub fn print_as_dice(n: usize, base: u8, list_length: usize, use_letters: bool)
     if use_letters {
         // We'll use zero-indexed values if sides_as_letters is
         // selected
-        match base {
-            // Values of 0 and 1 should have been caught earlier,
-            // so we'll panic! if we have them here
-            0 | 1 => panic!("Too few dice sides entered"),
-            2..=36 => padded_n
-                .chars()
-                .map(|ch| ch.to_string().to_uppercase())
-                .collect::<String>()
-                .trim()
-                .to_string(),
-            _ => panic!("Amount of dice sides received is too high"),
-        }
+        return Ok(padded_n.to_uppercase());
+    }
+    // We'll use 1-indexed values if sides_as_letters is NOT
+    // selected. Each character of `padded_n` is a digit from 0-9, then a-z.
+    let rolls = padded_n
+        .chars()
+        .filter_map(|ch| ch.to_digit(36))
+        .map(|digit| digit + 1);
+    if base <= 9 {
+        Ok(rolls.map(|roll| roll.to_string()).collect())
     } else {
-        // We'll use 1-indexed values if sides_as_letters is NOT
-        // selected
-        match base {
-            0 | 1 => panic!("Too few dice sides entered"),
-            2..=9 => padded_n
-                .chars()
-                .map(|ch| (ch.to_string().parse::<usize>().unwrap() + 1).to_string())
-                .collect::<String>(),
-            10..=36 => padded_n
-                .chars()
-                .map(|ch| char_to_digit(ch) + "-")
-                .collect::<String>()
-                .trim_end_matches('-')
-                .trim()
-                .to_string(),
-            _ => panic!("Amount of dice sides received is too high"),
-        }
+        Ok(rolls
+            .map(|roll| format!("{:02}", roll))
+            .collect::<Vec<String>>()
+            .join("-"))
+    }
+}
+
+/// Print a dice roll from a set of different dice, like a d20, d12, d10 and d8,
+/// rolled in that order. Each roll is one digit of a "mixed-radix" number, with the
+/// first die's roll the most significant, so a set of dice gives each word of a list
+/// as long as the product of their sides its own rolls. Note that the `n` parameter
+/// should be zero-indexed.
+///
+/// Rolls are printed like `print_as_dice` prints them: if every die has 9 or fewer
+/// sides, rolls are printed as single digits; otherwise, each roll is printed as two
+/// digits, separated by dashes. With `use_letters`, rolls are zero-indexed and printed
+/// as single characters (0-9, then A-Z).
+/// ```
+/// use tidy::dice::print_as_mixed_dice;
+/// assert_eq!(print_as_mixed_dice(0, &[20, 12, 10, 8], false), "01-01-01-01");
+/// assert_eq!(print_as_mixed_dice(19199, &[20, 12, 10, 8], false), "20-12-10-08");
+/// assert_eq!(print_as_mixed_dice(23, &[4, 6], false), "46");
+/// assert_eq!(print_as_mixed_dice(19199, &[20, 12, 10, 8], true), "JB97");
+/// ```
+pub fn print_as_mixed_dice(n: usize, dice: &[u8], use_letters: bool) -> String {
+    let mut rolls = vec![];
+    let mut remaining = n;
+    for sides in dice.iter().rev() {
+        let sides = (*sides).max(1) as usize;
+        rolls.push(remaining % sides);
+        remaining /= sides;
+    }
+    rolls.reverse();
+
+    if use_letters {
+        rolls
+            .iter()
+            .filter_map(|roll| char::from_digit(*roll as u32, 36))
+            .map(|ch| ch.to_ascii_uppercase())
+            .collect()
+    } else if dice.iter().all(|sides| *sides <= 9) {
+        rolls.iter().map(|roll| (roll + 1).to_string()).collect()
+    } else {
+        rolls
+            .iter()
+            .map(|roll| format!("{:02}", roll + 1))
+            .collect::<Vec<String>>()
+            .join("-")
     }
 }
 
-/// Convert 0-z inputted character to a 1-indexed, padded string ("01" to "36")
-fn char_to_digit(ch: char) -> String {
-    match ch {
-        '0' => "01",
-        '1' => "02",
-        '2' => "03",
-        '3' => "04",
-        '4' => "05",
-        '5' => "06",
-        '6' => "07",
-        '7' => "08",
-        '8' => "09",
-        '9' => "10",
-        'a' => "11",
-        'b' => "12",
-        'c' => "13",
-        'd' => "14",
-        'e' => "15",
-        'f' => "16",
-        'g' => "17",
-        'h' => "18",
-        'i' => "19",
-        'j' => "20",
-        'k' => "21",
-        'l' => "22",
-        'm' => "23",
-        'n' => "24",
-        'o' => "25",
-        'p' => "26",
-        'q' => "27",
-        'r' => "28",
-        's' => "29",
-        't' => "30",
-        'u' => "31",
-        'v' => "32",
-        'w' => "33",
-        'x' => "34",
-        'y' => "35",
-        'z' => "36",
-        _ => panic!("Unable to convert this dice number from a letter to a number."),
+/// Print the dice roll for the nth word of a list. One die means rolling it as many
+/// times as the list needs (see `print_as_dice`). More than one means rolling each
+/// of them once (see `print_as_mixed_dice`).
+pub fn print_dice_rolls(
+    n: usize,
+    dice: &[u8],
+    list_length: usize,
+    use_letters: bool,
+) -> Result<String, TidyError> {
+    match dice {
+        [sides] => print_as_dice(n, *sides, list_length, use_letters),
+        _ => Ok(print_as_mixed_dice(n, dice, use_letters)),
     }
-    .to_string()
+}
+
+/// Count the different rolls of a set of dice, which is the product of their sides.
+/// Returns `None` if there are too many to count.
+/// ```
+/// use tidy::dice::count_dice_rolls;
+/// assert_eq!(count_dice_rolls(&[20, 12, 10, 8]), Some(19_200));
+/// ```
+pub fn count_dice_rolls(dice: &[u8]) -> Option<usize> {
+    dice.iter()
+        .try_fold(1_usize, |rolls, sides| rolls.checked_mul(*sides as usize))
 }
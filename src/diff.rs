@@ -0,0 +1,297 @@
+//! Compare two versions of a word list: which words were added, removed or
+//! kept, how the lists' attributes changed, and, if the lists have dice rolls
+//! (or other codes) before each word, which codes now mean a different word.
+
+use crate::display_information::{ListAttributes, make_attributes};
+use crate::error::TidyError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// A code (like the dice roll "11111") whose word differs between the lists.
+/// A word of `None` means the code isn't on that list.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CodeChange {
+    pub code: String,
+    pub old_word: Option<String>,
+    pub new_word: Option<String>,
+}
+
+/// The differences between an old and a new version of a word list
+#[derive(Serialize)]
+pub struct ListDiff {
+    /// Words on the new list that aren't on the old one
+    pub added: Vec<String>,
+    /// Words on the old list that aren't on the new one
+    pub removed: Vec<String>,
+    /// Words on both lists
+    pub kept: Vec<String>,
+    /// Empty unless both lists have codes before their words
+    pub changed_codes: Vec<CodeChange>,
+    pub old_attributes: ListAttributes,
+    pub new_attributes: ListAttributes,
+}
+
+/// If a line is a code (like a dice roll or a card) followed by a tab or space
+/// and then a word, split it into the code and the word.
+/// ```
+/// use tidy::diff::split_code;
+/// assert_eq!(split_code("11111\tabacus"), Some(("11111", "abacus")));
+/// assert_eq!(split_code("03-11-01 dragon"), Some(("03-11-01", "dragon")));
+/// assert_eq!(split_code("abacus"), None);
+/// assert_eq!(split_code("ice cream"), None);
+/// ```
+pub fn split_code(line: &str) -> Option<(&str, &str)> {
+    let (code, word) = line.trim().split_once(['\t', ' '])?;
+    let is_code = code.chars().any(|c| c.is_ascii_digit())
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if is_code {
+        Some((code, word.trim()))
+    } else {
+        None
+    }
+}
+
+/// Split the lines of a list into codes and words, if every line has a code.
+/// Otherwise return `None`.
+fn split_codes(lines: &[String]) -> Option<Vec<(&str, &str)>> {
+    lines.iter().map(|line| split_code(line)).collect()
+}
+
+/// Find the words of a list, leaving out any codes before them, as well as blank lines
+fn find_words(lines: &[String], has_codes: bool) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| match split_code(line) {
+            Some((_code, word)) if has_codes => word.to_string(),
+            _ => line.trim().to_string(),
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Compare two versions of a word list. `attributes_level` works like the `-A`
+/// option: the higher it is, the more attributes are compared.
+///
+/// Returns an error if either list is empty, since an empty list doesn't have
+/// attributes to compare.
+pub fn diff_lists(
+    old_lines: &[String],
+    new_lines: &[String],
+    attributes_level: u8,
+) -> Result<ListDiff, TidyError> {
+    let old_lines: Vec<String> = old_lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .cloned()
+        .collect();
+    let new_lines: Vec<String> = new_lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .cloned()
+        .collect();
+    // Each list may or may not have codes, so that a list can be compared to
+    // a version of itself with (or without) dice rolls
+    let old_codes = split_codes(&old_lines);
+    let new_codes = split_codes(&new_lines);
+    let old_words = find_words(&old_lines, old_codes.is_some());
+    let new_words = find_words(&new_lines, new_codes.is_some());
+
+    let old_set: HashSet<&String> = old_words.iter().collect();
+    let new_set: HashSet<&String> = new_words.iter().collect();
+    let added = new_words
+        .iter()
+        .filter(|word| !old_set.contains(word))
+        .cloned()
+        .collect();
+    let removed = old_words
+        .iter()
+        .filter(|word| !new_set.contains(word))
+        .cloned()
+        .collect();
+    let kept = old_words
+        .iter()
+        .filter(|word| new_set.contains(word))
+        .cloned()
+        .collect();
+
+    let changed_codes = match (old_codes, new_codes) {
+        (Some(old_codes), Some(new_codes)) => find_changed_codes(&old_codes, &new_codes),
+        _ => vec![],
+    };
+
+    Ok(ListDiff {
+        added,
+        removed,
+        kept,
+        changed_codes,
+        old_attributes: make_attributes(&old_words, attributes_level, false, None)?,
+        new_attributes: make_attributes(&new_words, attributes_level, false, None)?,
+    })
+}
+
+/// Find the codes whose word differs between two lists that both have codes
+fn find_changed_codes(old_codes: &[(&str, &str)], new_codes: &[(&str, &str)]) -> Vec<CodeChange> {
+    let mut changed_codes = vec![];
+    let old_code_map: HashMap<&str, &str> = old_codes.iter().copied().collect();
+    let new_code_map: HashMap<&str, &str> = new_codes.iter().copied().collect();
+    for (code, old_word) in old_codes {
+        let new_word = new_code_map.get(code);
+        if new_word != Some(old_word) {
+            changed_codes.push(CodeChange {
+                code: code.to_string(),
+                old_word: Some(old_word.to_string()),
+                new_word: new_word.map(|word| word.to_string()),
+            });
+        }
+    }
+    for (code, new_word) in new_codes {
+        if !old_code_map.contains_key(code) {
+            changed_codes.push(CodeChange {
+                code: code.to_string(),
+                old_word: None,
+                new_word: Some(new_word.to_string()),
+            });
+        }
+    }
+    changed_codes
+}
+
+/// Print the differences between two lists for a person to read. Unlike most of
+/// Tidy's information, this goes to stdout, since it's what the user asked for.
+pub fn print_diff(diff: &ListDiff) {
+    println!("Added words ({})", diff.added.len());
+    for word in &diff.added {
+        println!("+ {}", word);
+    }
+    println!("\nRemoved words ({})", diff.removed.len());
+    for word in &diff.removed {
+        println!("- {}", word);
+    }
+    println!("\nKept words: {}", diff.kept.len());
+    if !diff.changed_codes.is_empty() {
+        println!(
+            "\nCodes with a different word ({})",
+            diff.changed_codes.len()
+        );
+        for change in &diff.changed_codes {
+            println!(
+                "{}\t{} -> {}",
+                change.code,
+                change.old_word.as_deref().unwrap_or("(none)"),
+                change.new_word.as_deref().unwrap_or("(none)")
+            );
+        }
+    }
+
+    println!("\n{:<28}{:<24}New list", "Attribute", "Old list");
+    println!("{:<28}{:<24}--------", "---------", "--------");
+    let old_rows = attribute_rows(&diff.old_attributes);
+    let new_rows = attribute_rows(&diff.new_attributes);
+    for ((name, old_value), (_name, new_value)) in old_rows.iter().zip(new_rows.iter()) {
+        // Mark attributes that changed
+        let marker = if old_value == new_value { "" } else { " *" };
+        println!("{:<28}{:<24}{}{}", name, old_value, new_value, marker);
+    }
+}
+
+/// The attributes of a list, labeled and formatted like `display_list_information`
+/// does. Attributes that weren't calculated are left out.
+fn attribute_rows(attributes: &ListAttributes) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("List length", format!("{} words", attributes.list_length)),
+        (
+            "Mean word length",
+            format!("{:.2} characters", attributes.mean_word_length),
+        ),
+        (
+            "Length of shortest word",
+            format!(
+                "{} ({})",
+                attributes.shortest_word_length, attributes.shortest_word_example
+            ),
+        ),
+        (
+            "Length of longest word",
+            format!(
+                "{} ({})",
+                attributes.longest_word_length, attributes.longest_word_example
+            ),
+        ),
+    ];
+    let optional_rows = [
+        (
+            "Free of prefix words?",
+            attributes.is_free_of_prefix_words.map(|b| b.to_string()),
+        ),
+        (
+            "Free of suffix words?",
+            attributes.is_free_of_suffix_words.map(|b| b.to_string()),
+        ),
+        (
+            "Uniquely decodable?",
+            attributes.is_uniquely_decodable.map(|b| b.to_string()),
+        ),
+    ];
+    rows.extend(
+        optional_rows
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name, value))),
+    );
+    rows.extend([
+        (
+            "Entropy per word",
+            format!("{:.3} bits", attributes.entropy_per_word),
+        ),
+        (
+            "Efficiency per character",
+            format!("{:.3} bits", attributes.efficiency_per_character),
+        ),
+        (
+            "Assumed entropy per char",
+            format!("{:.3} bits", attributes.assumed_entropy_per_character),
+        ),
+        (
+            "Above brute force line?",
+            attributes.is_above_brute_force_line.to_string(),
+        ),
+        (
+            "Above Shannon line?",
+            attributes.is_above_shannon_line.to_string(),
+        ),
+    ]);
+    let optional_rows = [
+        (
+            "Shortest edit distance",
+            attributes.shortest_edit_distance.map(|d| d.to_string()),
+        ),
+        (
+            "Mean edit distance",
+            attributes.mean_edit_distance.map(|d| format!("{:.3}", d)),
+        ),
+        (
+            "Longest shared prefix",
+            attributes.longest_shared_prefix.map(|p| p.to_string()),
+        ),
+        (
+            "Unique character prefix",
+            attributes.unique_character_prefix.map(|p| p.to_string()),
+        ),
+    ];
+    rows.extend(
+        optional_rows
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name, value))),
+    );
+    rows.push((
+        "Kraft-McMillan inequality",
+        attributes.kraft_mcmillan.to_string(),
+    ));
+    rows
+}
+
+/// Print the differences between two lists as JSON, for other programs (like CI jobs)
+pub fn print_diff_as_json(diff: &ListDiff) {
+    // Serializing these types can't fail, as they're all strings and numbers
+    println!("{}", serde_json::to_string(diff).unwrap_or_default());
+}
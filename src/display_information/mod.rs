@@ -3,8 +3,8 @@
 pub mod uniquely_decodable;
 use crate::count_characters;
 use crate::display_information::uniquely_decodable::is_uniquely_decodable;
-use crate::parse_delimiter;
-use crate::split_and_vectorize;
+use crate::error::TidyError;
+use crate::metadata::{TabularOptions, split_off_metadata};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -49,21 +49,31 @@ impl fmt::Display for KraftMcmillanOutcome {
     }
 }
 
-fn make_attributes(list: &[String], level: u8, samples: bool) -> ListAttributes {
+/// Work out the attributes of a list. The higher the level, the more (and
+/// slower to calculate) attributes. Returns an error if the list is empty.
+pub fn make_attributes(
+    list: &[String],
+    level: u8,
+    samples: bool,
+    seed: Option<u64>,
+) -> Result<ListAttributes, TidyError> {
     let samples = if samples {
-        Some(generate_samples(list))
+        Some(generate_samples(list, seed)?)
     } else {
         None
     };
+    let empty_list_error = || TidyError::EmptyList {
+        operation: "find the attributes".to_string(),
+    };
     let shortest_word_example = list
         .iter()
         .min_by(|a, b| count_characters(a).cmp(&count_characters(b)))
-        .unwrap()
+        .ok_or_else(empty_list_error)?
         .to_string();
     let longest_word_example = list
         .iter()
         .max_by(|a, b| count_characters(a).cmp(&count_characters(b)))
-        .unwrap()
+        .ok_or_else(empty_list_error)?
         .to_string();
 
     let is_free_of_prefix_words = if level >= 2 {
@@ -111,7 +121,7 @@ fn make_attributes(list: &[String], level: u8, samples: bool) -> ListAttributes
     } else {
         None
     };
-    ListAttributes {
+    Ok(ListAttributes {
         list_length: list.len(),
         mean_word_length: mean_word_length(list),
         entropy_per_word: calc_entropy_per_word(list.len()),
@@ -132,7 +142,7 @@ fn make_attributes(list: &[String], level: u8, samples: bool) -> ListAttributes
         unique_character_prefix,
         kraft_mcmillan: satisfies_kraft_mcmillan(list),
         samples,
-    }
+    })
 }
 
 /// If user gets a passphrase consisting entirely of shortest words,
@@ -143,10 +153,14 @@ fn make_attributes(list: &[String], level: u8, samples: bool) -> ListAttributes
 /// Note that this slightly obscure method of calculation ensures that floating-point arithmetic is
 /// not used, thus ensuring a higher level of accuracy.
 fn is_above_brute_force_line(list: &[String]) -> bool {
-    let g: i32 = 26; // roughly: assumed alphabet length
+    let g: u64 = 26; // roughly: assumed alphabet length
     let shortest_word_length = get_shortest_word_length(list) as u32;
-    let list_length = list.len() as i32;
-    list_length as f64 <= g.pow(shortest_word_length).into()
+    let list_length = list.len() as u64;
+    // If the number of possible strings is too big to count, no list is longer
+    match g.checked_pow(shortest_word_length) {
+        Some(number_of_possible_strings) => list_length <= number_of_possible_strings,
+        None => true,
+    }
 }
 
 /// In 1951, Claude Shannon estimated that English words only have
@@ -166,20 +180,27 @@ fn is_above_shannon_line(list: &[String]) -> bool {
 ///
 /// We just want to "display" this information, rather than print it to files
 /// or stdout, so we use `eprintln!`
+///
+/// Returns an error if the list is empty, since an empty list doesn't have
+/// many attributes to speak of.
+#[allow(clippy::too_many_arguments)]
 pub fn display_list_information(
     list: &[String],
     level: u8,
     attributes_as_json: bool,
     ignore_ending_metadata_delimiter: Option<char>,
     ignore_starting_metadata_delimiter: Option<char>,
+    tabular: Option<TabularOptions>,
     samples: bool,
-) {
+    seed: Option<u64>,
+) -> Result<(), TidyError> {
     let list = make_list_free_of_metadata(
         list,
-        ignore_starting_metadata_delimiter,
         ignore_ending_metadata_delimiter,
+        ignore_starting_metadata_delimiter,
+        tabular,
     );
-    let list_attributes = make_attributes(&list, level, samples);
+    let list_attributes = make_attributes(&list, level, samples, seed)?;
     if attributes_as_json {
         print_attributes_as_json(&list_attributes);
     } else {
@@ -265,6 +286,7 @@ pub fn display_list_information(
             print_samples(samples)
         }
     }
+    Ok(())
 }
 
 fn print_attributes_as_json(list_attributes: &ListAttributes) {
@@ -272,52 +294,44 @@ fn print_attributes_as_json(list_attributes: &ListAttributes) {
     eprintln!("{}", json);
 }
 
-fn make_list_free_of_metadata(
+/// Remove any metadata from every line of a list, leaving just the words
+pub fn make_list_free_of_metadata(
     list: &[String],
     ignore_ending_metadata_delimiter: Option<char>,
     ignore_starting_metadata_delimiter: Option<char>,
+    tabular: Option<TabularOptions>,
 ) -> Vec<String> {
-    match (
-        ignore_ending_metadata_delimiter,
-        ignore_starting_metadata_delimiter,
-    ) {
-        (Some(delimiter), None) => {
-            let delimiter = parse_delimiter(delimiter).unwrap();
-            let mut just_the_words = vec![];
-            for word in list {
-                let split_vec = split_and_vectorize(word, &delimiter.to_string());
-                just_the_words.push(split_vec[1].to_string());
-            }
-            just_the_words
-        }
-        (None, Some(delimiter)) => {
-            let delimiter = parse_delimiter(delimiter).unwrap();
-            let mut just_the_words = vec![];
-            for word in list {
-                let split_vec = split_and_vectorize(word, &delimiter.to_string());
-                just_the_words.push(split_vec[0].to_string());
-            }
-            just_the_words
-        }
-        (Some(ref _delimiter1), Some(ref _delimiter2)) => {
-            panic!("Can't ignore metadata on both sides currently")
-        }
-        (None, None) => list.to_vec(),
-    }
+    list.iter()
+        .map(|line| {
+            split_off_metadata(
+                line,
+                ignore_ending_metadata_delimiter,
+                ignore_starting_metadata_delimiter,
+                tabular,
+            )
+            .0
+        })
+        .collect()
 }
 
 use rand::prelude::IndexedRandom;
 /// Print 5 sample 6-word passphrases from the newly created
-/// word list.
-pub fn generate_samples(list: &[String]) -> Vec<String> {
+/// word list. Give a seed to get the same samples every time.
+/// Returns an error if the list is empty.
+pub fn generate_samples(list: &[String], seed: Option<u64>) -> Result<Vec<String>, TidyError> {
+    let mut rng = crate::make_rng(seed);
     let mut samples: Vec<String> = vec![];
     for _n in 0..30 {
-        match list.choose(&mut rand::rng()) {
+        match list.choose(&mut rng) {
             Some(word) => samples.push(word.to_string()),
-            None => panic!("Couldn't pick a random word"),
+            None => {
+                return Err(TidyError::EmptyList {
+                    operation: "generate sample passphrases".to_string(),
+                });
+            }
         }
     }
-    samples
+    Ok(samples)
 }
 
 /// Calculate the entropy per word of a word list, given its size.
@@ -518,8 +532,8 @@ pub fn satisfies_kraft_mcmillan(list: &[String]) -> KraftMcmillanOutcome {
     let alphabet_size = count_unique_characters(list);
     let mut running_total: f64 = 0.0;
     for word in list {
-        running_total +=
-            1.0 / (alphabet_size.pow(count_characters(word).try_into().unwrap()) as f64);
+        // Long words make this power too big for an integer, so use a float
+        running_total += 1.0 / (alphabet_size as f64).powi(count_characters(word) as i32);
     }
     if running_total <= 1.0 {
         KraftMcmillanOutcome::Satisfied
@@ -563,7 +577,7 @@ pub fn mean_word_length(list: &[String]) -> f32 {
 fn print_samples(samples: Vec<String>) {
     eprintln!("\nWord samples");
     eprintln!("------------");
-    for n in 0..30 {
+    for (n, sample) in samples.iter().enumerate().take(30) {
         if n != 0 && n % 6 == 0 {
             // if we're at the end of the 6th word,
             // print a newline
@@ -573,7 +587,7 @@ fn print_samples(samples: Vec<String>) {
             // word
             eprint!(" ");
         }
-        eprint!("{}", samples[n]);
+        eprint!("{}", sample);
     }
     eprintln!();
 }
@@ -0,0 +1,120 @@
+//! The errors Tidy can run into. Library functions return these rather than
+//! panicking, so that programs that embed Tidy can decide what to do with them,
+//! and so that the command-line tool can print a useful message.
+
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum TidyError {
+    /// Couldn't open or read a file (or stdin, if the path is "-")
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// Couldn't create or write to a file (or stdout)
+    Write {
+        path: Option<PathBuf>,
+        source: std::io::Error,
+    },
+    /// A line of an inputted file isn't in the format Tidy expected
+    MalformedLine {
+        path: PathBuf,
+        line_number: usize,
+        line: String,
+        expected: String,
+    },
+    /// A whole file (like a recipe) couldn't be parsed
+    MalformedFile { path: PathBuf, reason: String },
+    /// The user gave an option a value Tidy can't use
+    InvalidOption {
+        option: String,
+        value: String,
+        reason: String,
+    },
+    /// Two options were given that can't be used together
+    ConflictingOptions {
+        options: Vec<String>,
+        reason: String,
+    },
+    /// Tried to do something that needs at least one word (like print
+    /// attributes or samples) with an empty list
+    EmptyList { operation: String },
+}
+
+impl TidyError {
+    /// Convenient way to make an `InvalidOption` error
+    pub fn invalid_option(option: &str, value: &str, reason: &str) -> Self {
+        TidyError::InvalidOption {
+            option: option.to_string(),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for TidyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TidyError::Read { path, source } => {
+                write!(f, "Error reading file {:?}: {}", path, source)
+            }
+            TidyError::Write {
+                path: Some(path),
+                source,
+            } => write!(f, "Error writing to file {:?}: {}", path, source),
+            TidyError::Write { path: None, source } => {
+                write!(f, "Error writing to standard output: {}", source)
+            }
+            TidyError::MalformedLine {
+                path,
+                line_number,
+                line,
+                expected,
+            } => write!(
+                f,
+                "Error: Line {} of file {:?} should be {}, but is {:?}",
+                line_number, path, expected, line
+            ),
+            TidyError::MalformedFile { path, reason } => {
+                write!(f, "Error parsing file {:?}: {}", path, reason)
+            }
+            TidyError::InvalidOption {
+                option,
+                value,
+                reason,
+            } => write!(
+                f,
+                "Error: Invalid value {:?} for {}: {}",
+                value, option, reason
+            ),
+            TidyError::ConflictingOptions { options, reason } => {
+                write!(
+                    f,
+                    "Error: Can't use {} together: {}",
+                    options.join(" and "),
+                    reason
+                )
+            }
+            TidyError::EmptyList { operation } => {
+                write!(f, "Error: Can't {} of an empty list", operation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TidyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TidyError::Read { source, .. } | TidyError::Write { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// The command-line tool reports errors as strings
+impl From<TidyError> for String {
+    fn from(error: TidyError) -> Self {
+        error.to_string()
+    }
+}
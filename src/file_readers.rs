@@ -1,72 +1,313 @@
+use crate::compression::decompress_if_needed;
+use crate::decoding::{InputEncoding, ReadOptions, decode_numbered_lines, parse_encoding};
+use crate::error::TidyError;
+use crate::hunspell::{HunspellOptions, parse_affix_file};
 use crate::split_and_vectorize;
 use std::fs::File;
+use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::io::Read;
+use std::io::stdin;
+use std::path::Path;
 use std::path::PathBuf;
 
+/// Following the common command-line convention, a path of "-"
+/// means "read from standard input" rather than from a file. This lets
+/// Tidy sit in the middle of a shell pipeline.
+pub fn is_stdin(filename: &Path) -> bool {
+    filename == Path::new("-")
+}
+
+/// Open the given path for buffered reading, falling back to stdin
+/// if the path is "-". Compressed (gzip, xz or zstd) input is detected
+/// and decompressed as it's read.
+fn open_for_reading(filename: &Path) -> Result<Box<dyn BufRead>, TidyError> {
+    open_input(filename, &mut Some(stdin()))
+}
+
+/// Like `open_for_reading`, but "-" reads from the given reader rather than
+/// from stdin (which can only be read once).
+fn open_input<R: Read + 'static>(
+    filename: &Path,
+    stdin: &mut Option<R>,
+) -> Result<Box<dyn BufRead>, TidyError> {
+    let read_error = |source| TidyError::Read {
+        path: filename.to_path_buf(),
+        source,
+    };
+    let reader: Box<dyn BufRead> = if is_stdin(filename) {
+        match stdin.take() {
+            Some(stdin) => Box::new(BufReader::new(stdin)),
+            None => return Err(read_error(io::Error::other("stdin was already read"))),
+        }
+    } else {
+        Box::new(BufReader::new(File::open(filename).map_err(read_error)?))
+    };
+    decompress_if_needed(reader).map_err(read_error)
+}
+
+/// Read all lines of the given file (or stdin), decoding them from the
+/// encoding the user specified. Reports any lines that were not valid
+/// in that encoding, and so were either skipped or repaired.
+fn read_lines(filename: &Path, read_options: &ReadOptions) -> Result<Vec<String>, TidyError> {
+    let reader = open_for_reading(filename)?;
+    Ok(
+        read_numbered_lines(reader, filename, None, None, read_options)?
+            .into_iter()
+            .map(|(_line_number, line)| line)
+            .collect(),
+    )
+}
+
+/// Like `read_lines`, but read from an opened reader and keep the (1-indexed)
+/// line number of each line in the file, so that we can tell users where any
+/// problems are. Can also skip rows at the start and end of the file.
+fn read_numbered_lines(
+    reader: Box<dyn BufRead>,
+    filename: &Path,
+    skip_rows_start: Option<usize>,
+    skip_rows_end: Option<usize>,
+    read_options: &ReadOptions,
+) -> Result<Vec<(usize, String)>, TidyError> {
+    let (mut numbered_lines, report) =
+        decode_numbered_lines(reader, read_options).map_err(|source| TidyError::Read {
+            path: filename.to_path_buf(),
+            source,
+        })?;
+    if !report.skipped_lines.is_empty() {
+        eprintln!(
+            "WARNING: Skipped {} line(s) of file {:?} that were not valid {}: line(s) {}. Use --lossy to keep them or --encoding to change the encoding.",
+            report.skipped_lines.len(),
+            filename,
+            report.encoding,
+            format_line_numbers(&report.skipped_lines)
+        );
+    }
+    if !report.repaired_lines.is_empty() {
+        eprintln!(
+            "WARNING: Replaced invalid characters in {} line(s) of file {:?}: line(s) {}",
+            report.repaired_lines.len(),
+            filename,
+            format_line_numbers(&report.repaired_lines)
+        );
+    }
+    // Skip rows before looking for PGP armor, so that users who skip a signature's
+    // header and footer themselves don't lose words from the list
+    let rows_to_keep_end = numbered_lines
+        .len()
+        .saturating_sub(skip_rows_end.unwrap_or(0));
+    numbered_lines.truncate(rows_to_keep_end);
+    numbered_lines.drain(..skip_rows_start.unwrap_or(0).min(rows_to_keep_end));
+    if let Some((body_start, body_end)) = find_pgp_body(&numbered_lines) {
+        numbered_lines = numbered_lines
+            .drain(body_start..body_end)
+            .map(|(line_number, line)| (line_number, unescape_dashes(line)))
+            .collect();
+    }
+    if let Some(ref comment_prefix) = read_options.comment_prefix {
+        numbered_lines
+            .retain(|(_line_number, line)| !line.trim_start().starts_with(comment_prefix.as_str()));
+    }
+    Ok(numbered_lines)
+}
+
+/// Some word lists (like the EFF's) are published as PGP "clearsigned" messages,
+/// with the list sandwiched between a header and a signature. If given lines like
+/// that, return only the signed body. Otherwise, return the lines unchanged.
+///
+/// Clearsigning "dash-escapes" any line of the body that starts with a dash, by
+/// adding "- " to the front of it, so we remove that too.
+/// See <https://www.rfc-editor.org/rfc/rfc4880#section-7>.
+/// ```
+/// use tidy::file_readers::strip_pgp_armor;
+/// let lines: Vec<String> = [
+///     "-----BEGIN PGP SIGNED MESSAGE-----",
+///     "Hash: SHA256",
+///     "",
+///     "abacus",
+///     "- -dash",
+///     "-----BEGIN PGP SIGNATURE-----",
+///     "iQIzBAEBCAAdFiEE",
+///     "-----END PGP SIGNATURE-----",
+/// ]
+/// .iter()
+/// .map(|line| line.to_string())
+/// .collect();
+/// assert_eq!(strip_pgp_armor(lines), vec!["abacus", "-dash"]);
+/// ```
+pub fn strip_pgp_armor(lines: Vec<String>) -> Vec<String> {
+    let numbered_lines: Vec<(usize, String)> = (1..).zip(lines).collect();
+    match find_pgp_body(&numbered_lines) {
+        Some((body_start, body_end)) => numbered_lines[body_start..body_end]
+            .iter()
+            .map(|(_line_number, line)| unescape_dashes(line.to_string()))
+            .collect(),
+        None => numbered_lines
+            .into_iter()
+            .map(|(_line_number, line)| line)
+            .collect(),
+    }
+}
+
+/// Find where the signed body of a PGP clearsigned message starts and ends,
+/// if these lines are one.
+fn find_pgp_body(numbered_lines: &[(usize, String)]) -> Option<(usize, usize)> {
+    let start = numbered_lines
+        .iter()
+        .position(|(_, line)| line.trim_end() == "-----BEGIN PGP SIGNED MESSAGE-----")?;
+    // Armor headers (like "Hash: SHA256") end with a blank line
+    let body_start = start
+        + numbered_lines[start..]
+            .iter()
+            .position(|(_, line)| line.trim().is_empty())?
+        + 1;
+    let body_end = body_start
+        + numbered_lines[body_start..]
+            .iter()
+            .take_while(|(_, line)| line.trim_end() != "-----BEGIN PGP SIGNATURE-----")
+            .count();
+    Some((body_start, body_end))
+}
+
+fn unescape_dashes(line: String) -> String {
+    match line.strip_prefix("- ") {
+        Some(unescaped_line) => unescaped_line.to_string(),
+        None => line,
+    }
+}
+
+fn format_line_numbers(line_numbers: &[usize]) -> String {
+    line_numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 /// Takes a slice of `PathBuf`s representing the word list(s)
 /// that the user has inputted to the program. Then iterates
 /// through each file and addes each line to `Vec<String>`. (Blank
 /// lines and duplicate links will be handled elsewhere.)
+///
+/// A filename of "-" reads from stdin instead of a file.
 pub fn make_vec_from_filenames(
     filenames: &[PathBuf],
     concat_lists: bool,
     skip_rows_start: Option<usize>,
     skip_rows_end: Option<usize>,
-) -> Vec<String> {
+    read_options: &ReadOptions,
+) -> Result<Vec<String>, TidyError> {
+    let word_lists_by_file =
+        read_word_lists_by_file(filenames, skip_rows_start, skip_rows_end, read_options)?;
+    Ok(combine_lists(word_lists_by_file, concat_lists, None))
+}
+
+/// Read each of the given files into its own `Vec<String>`, skipping
+/// any rows the user asked us to skip. Useful if we need to handle each
+/// file individually (like removing header rows) before combining them.
+pub fn read_word_lists_by_file(
+    filenames: &[PathBuf],
+    skip_rows_start: Option<usize>,
+    skip_rows_end: Option<usize>,
+    read_options: &ReadOptions,
+) -> Result<Vec<Vec<String>>, TidyError> {
+    read_word_lists_by_file_with_stdin(
+        filenames,
+        stdin(),
+        skip_rows_start,
+        skip_rows_end,
+        read_options,
+    )
+}
+
+/// Like `read_word_lists_by_file`, but a filename of "-" reads from the given
+/// reader rather than from stdin.
+pub fn read_word_lists_by_file_with_stdin<R: Read + 'static>(
+    filenames: &[PathBuf],
+    stdin: R,
+    skip_rows_start: Option<usize>,
+    skip_rows_end: Option<usize>,
+    read_options: &ReadOptions,
+) -> Result<Vec<Vec<String>>, TidyError> {
+    let mut stdin = Some(stdin);
     let mut word_lists_by_file: Vec<Vec<String>> = [].to_vec();
     for filename in filenames {
-        let f = match File::open(filename) {
-            Ok(file) => file,
-            Err(e) => panic!("Error opening file {:?}: {}", filename, e),
-        };
-        let file = BufReader::new(&f);
-        let mut raw_lines = vec![];
-        for line in file.lines() {
-            let l = match line {
-                Ok(l) => l,
-                Err(e) => {
-                    eprintln!(
-                        "Error reading a line from file {:?}: {}\nWill continue reading file.",
-                        filename, e
-                    );
-                    continue;
-                }
-            };
-            raw_lines.push(l);
+        let reader = open_input(filename, &mut stdin)?;
+        let word_list_from_this_file: Vec<String> = read_numbered_lines(
+            reader,
+            filename,
+            skip_rows_start,
+            skip_rows_end,
+            read_options,
+        )?
+        .into_iter()
+        .map(|(_line_number, line)| line)
+        .collect();
+        word_lists_by_file.push(word_list_from_this_file);
+    }
+    Ok(word_lists_by_file)
+}
+
+/// Read each of the given Hunspell dictionaries (`.dic` files) into its own
+/// `Vec<String>`. Each `.dic` file needs an affix file (`.aff`) of the same
+/// name next to it, like `en_US.dic` and `en_US.aff`.
+///
+/// Depending on the given options, we either take only the stems from the
+/// `.dic` file or expand them into all their word forms using the affix rules.
+pub fn read_hunspell_dictionaries_by_file(
+    filenames: &[PathBuf],
+    hunspell_options: &HunspellOptions,
+    read_options: &ReadOptions,
+) -> Result<Vec<Vec<String>>, TidyError> {
+    let mut word_lists_by_file: Vec<Vec<String>> = vec![];
+    for filename in filenames {
+        if is_stdin(filename) {
+            return Err(TidyError::ConflictingOptions {
+                options: vec!["--hunspell".to_string(), "stdin".to_string()],
+                reason: "a Hunspell dictionary needs an affix file next to it".to_string(),
+            });
         }
-        let size_of_raw_lines = raw_lines.len();
-        let mut word_list_from_this_file = [].to_vec();
-        for (line_number, line) in raw_lines.into_iter().enumerate() {
-            match (skip_rows_start, skip_rows_end) {
-                (Some(skip_rows_start), Some(skip_rows_end)) => {
-                    if line_number >= skip_rows_start
-                        && line_number < size_of_raw_lines - skip_rows_end
-                    {
-                        word_list_from_this_file.push(line);
-                    }
-                }
-                (Some(skip_rows_start), None) => {
-                    if line_number >= skip_rows_start {
-                        word_list_from_this_file.push(line);
-                    }
-                }
-                (None, Some(skip_rows_end)) => {
-                    if line_number < size_of_raw_lines - skip_rows_end {
-                        word_list_from_this_file.push(line);
-                    }
-                }
-                (None, None) => word_list_from_this_file.push(line),
+        let affix_filename = filename.with_extension("aff");
+        // The affix file says which encoding both files are in, so first find its
+        // SET line by reading it as Latin-1, in which any bytes are valid
+        let latin1_options = ReadOptions {
+            encoding: InputEncoding::Latin1,
+            ..read_options.clone()
+        };
+        let declared_encoding =
+            parse_affix_file(&read_lines(&affix_filename, &latin1_options)?).encoding;
+        let mut read_options = read_options.clone();
+        if let Some(ref encoding) = declared_encoding {
+            match parse_encoding(encoding) {
+                Ok(encoding) => read_options.encoding = encoding,
+                Err(_) => eprintln!(
+                    "WARNING: Affix file {:?} uses encoding {}, which Tidy can't read. Reading as {} instead.",
+                    affix_filename, encoding, read_options.encoding
+                ),
             }
         }
-        word_lists_by_file.push(word_list_from_this_file);
+        let affix_file = parse_affix_file(&read_lines(&affix_filename, &read_options)?);
+        let dictionary_lines = read_lines(filename, &read_options)?;
+        word_lists_by_file.push(affix_file.expand_dictionary(&dictionary_lines, hunspell_options));
     }
-    // Finally, concatenate or "blend" words into one Vec<String>,
-    // depending on user's choice via flag
-    if !concat_lists {
-        blend_lists(&word_lists_by_file)
-    } else {
+    Ok(word_lists_by_file)
+}
+
+/// Concatenate or "blend" words from multiple files into one Vec<String>,
+/// depending on user's choice via flag. If given weights, blend the lists
+/// using them (see `blend_lists_with_weights`).
+pub fn combine_lists(
+    word_lists_by_file: Vec<Vec<String>>,
+    concat_lists: bool,
+    weights: Option<&[usize]>,
+) -> Vec<String> {
+    if concat_lists {
         concatenate_lists(word_lists_by_file)
+    } else if let Some(weights) = weights {
+        blend_lists_with_weights(&word_lists_by_file, weights)
+    } else {
+        blend_lists(&word_lists_by_file)
     }
 }
 
@@ -81,20 +322,29 @@ pub fn concatenate_lists(word_lists_by_file: Vec<Vec<String>>) -> Vec<String> {
 
 /// "Blend" words together one at a time, like dealing cards in reverse
 pub fn blend_lists(word_lists_by_file: &[Vec<String>]) -> Vec<String> {
-    let mut size_of_longest_vector = 0;
-    for word_list in word_lists_by_file {
-        if size_of_longest_vector < word_list.len() {
-            size_of_longest_vector = word_list.len();
-        }
-    }
-    // "Blend" words together one at a time, like dealing cards in reverse
-    let mut blended = [].to_vec();
-    for i in 0..size_of_longest_vector {
-        for list in word_lists_by_file {
-            if list.len() > i {
-                // Dunno how to not call clone here...
-                blended.push(list[i].clone());
-            }
+    blend_lists_with_weights(word_lists_by_file, &vec![1; word_lists_by_file.len()])
+}
+
+/// Like `blend_lists`, but on each "deal" take as many words from each list
+/// as its weight. So weights of 3 and 1 take three words from the first list,
+/// then one word from the second, then three more from the first, and so on.
+/// Once a list runs out of words, the remaining lists keep dealing.
+///
+/// Lists without a weight (or with a weight of 0) get a weight of 1.
+pub fn blend_lists_with_weights(
+    word_lists_by_file: &[Vec<String>],
+    weights: &[usize],
+) -> Vec<String> {
+    let mut blended = vec![];
+    // Where we are in each list
+    let mut positions = vec![0; word_lists_by_file.len()];
+    let total_words: usize = word_lists_by_file.iter().map(|list| list.len()).sum();
+    while blended.len() < total_words {
+        for (i, list) in word_lists_by_file.iter().enumerate() {
+            let weight = weights.get(i).copied().unwrap_or(1).max(1);
+            let end = (positions[i] + weight).min(list.len());
+            blended.extend_from_slice(&list[positions[i]..end]);
+            positions[i] = end;
         }
     }
     blended
@@ -102,38 +352,41 @@ pub fn blend_lists(word_lists_by_file: &[Vec<String>]) -> Vec<String> {
 
 /// Like `make_vec_from_filenames`, this function takes a slice of `PathBuf`s of
 /// files. But in this case these files represent lists of homophones that the
-/// user wants to make sure aren't both on the resulting list.
+/// user wants to make sure aren't all on the resulting list.
 ///
 /// These homophone files are expected to be formatted such that each line of the file
-/// is `homophone1,homophone2`.
+/// is a group of two or more homophones separated by commas, like `sun,son` or
+/// `to,too,two`. Blank lines are skipped.
 ///
-/// This function produces a Vector of tuples of strings, representing the
-/// homophone pairs.
-pub fn read_homophones_list_from_filenames(filenames: &[PathBuf]) -> Vec<(String, String)> {
-    let mut homophones_list: Vec<(String, String)> = vec![];
+/// This function produces a Vector of groups of homophones. If a line has fewer than
+/// two homophones on it, returns an error with the file and line number.
+pub fn read_homophones_list_from_filenames(
+    filenames: &[PathBuf],
+    read_options: &ReadOptions,
+) -> Result<Vec<Vec<String>>, TidyError> {
+    let mut homophones_list: Vec<Vec<String>> = vec![];
     for filename in filenames {
-        let f = match File::open(filename) {
-            Ok(file) => file,
-            Err(e) => panic!("Error opening file {:?}: {}", filename, e),
-        };
-        let file = BufReader::new(&f);
-        for line in file.lines() {
-            let l = match line {
-                Ok(l) => l,
-                Err(e) => {
-                    eprintln!(
-                        "Error reading a line from file {:?}: {}\nWill continue reading file.",
-                        filename, e
-                    );
-                    continue;
-                }
-            };
-            let pair: (String, String) = (
-                split_and_vectorize(&l, ",")[0].trim().to_string(),
-                split_and_vectorize(&l, ",")[1].trim().to_string(),
-            );
-            homophones_list.push(pair);
+        let reader = open_for_reading(filename)?;
+        for (line_number, line) in read_numbered_lines(reader, filename, None, None, read_options)?
+        {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let group: Vec<String> = split_and_vectorize(&line, ",")
+                .into_iter()
+                .map(|homophone| homophone.trim().to_string())
+                .filter(|homophone| !homophone.is_empty())
+                .collect();
+            if group.len() < 2 {
+                return Err(TidyError::MalformedLine {
+                    path: filename.to_path_buf(),
+                    line_number,
+                    line,
+                    expected: "two or more homophones separated by commas".to_string(),
+                });
+            }
+            homophones_list.push(group);
         }
     }
-    homophones_list
+    Ok(homophones_list)
 }
@@ -1,9 +1,69 @@
-use crate::cards::print_as_cards;
-use crate::dice::print_as_dice;
+use crate::cards::{CardOptions, display_card_information, print_card_code};
+use crate::compression::compress_for_path;
+use crate::dice::print_dice_rolls;
 use crate::display_information::display_list_information;
+use crate::error::TidyError;
+use crate::formats::{LineTemplate, OutputFormat, make_list_table, write_list_table};
+use crate::metadata::{Metadata, TabularOptions, join_record, split_off_metadata};
+use serde::{Serialize, Serializer};
+use std::fmt;
 use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// What to end each line of the new list with
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    /// Line feed (`\n`), as on Linux and macOS
+    #[default]
+    Lf,
+    /// Carriage return and line feed (`\r\n`), as on Windows
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "lf"),
+            LineEnding::Crlf => write!(f, "crlf"),
+        }
+    }
+}
+
+// Serialized as it'd be typed on the command line, for recipes
+impl Serialize for LineEnding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse user's `--line-ending` input (case insensitive).
+/// ```
+/// use tidy::file_writer::{parse_line_ending, LineEnding};
+/// assert_eq!(parse_line_ending("CRLF"), Ok(LineEnding::Crlf));
+/// assert_eq!(parse_line_ending("windows"), Ok(LineEnding::Crlf));
+/// assert!(parse_line_ending("cr").is_err());
+/// ```
+pub fn parse_line_ending(input: &str) -> Result<LineEnding, String> {
+    match input.to_lowercase().as_str() {
+        "lf" | "unix" => Ok(LineEnding::Lf),
+        "crlf" | "windows" => Ok(LineEnding::Crlf),
+        _ => Err(format!(
+            "Unknown line ending {:?}. Options are lf and crlf",
+            input
+        )),
+    }
+}
 
 #[derive(Default, Debug, Clone)]
 pub struct PrintRequest {
@@ -11,18 +71,28 @@ pub struct PrintRequest {
     pub dry_run: bool,
     pub quiet: bool,
     pub output: Option<PathBuf>,
-    pub dice_sides: Option<u8>,
-    pub cards: bool,
+    /// Sides of the dice to print rolls of. One die is rolled as many times as needed;
+    /// more than one are each rolled once.
+    pub dice_sides: Option<Vec<u8>>,
+    /// How to print card codes, if at all
+    pub cards: Option<CardOptions>,
     pub print_dice_sides_as_their_base: bool,
     pub attributes: u8,
     pub attributes_as_json: bool,
     pub samples: bool,
+    pub seed: Option<u64>,
     pub ignore_before_delimiter: Option<char>,
     pub ignore_after_delimiter: Option<char>,
+    pub tabular: Option<TabularOptions>,
+    pub header_row: Option<String>,
+    pub format: OutputFormat,
+    /// Template for each line of a plain text list, like `{index}:{dice} {word}`
+    pub template: Option<LineTemplate>,
+    pub line_ending: LineEnding,
 }
 
 /// Print to terminal or file
-pub fn print_list(print_req: PrintRequest) {
+pub fn print_list(print_req: PrintRequest) -> Result<(), TidyError> {
     if !print_req.quiet {
         if print_req.tidied_list.is_empty() {
             eprintln!(
@@ -34,36 +104,26 @@ pub fn print_list(print_req: PrintRequest) {
     }
     if !print_req.dry_run {
         match print_req.output {
-            Some(output) => {
+            Some(ref output) => {
                 // Print to file
-                print_list_to_file(
-                    &print_req.tidied_list,
-                    output,
-                    print_req.cards,
-                    print_req.dice_sides,
-                    print_req.print_dice_sides_as_their_base,
-                );
-            }
-            // If no output file destination, print resulting list, word by word,
-            // to println (which goes to stdout, allowing use of > on command line)
-            None => {
-                for (i, word) in print_req.tidied_list.iter().enumerate() {
-                    if let Some(dice_sides) = print_req.dice_sides {
-                        print!(
-                            "{:}\t",
-                            print_as_dice(
-                                i,
-                                dice_sides,
-                                print_req.tidied_list.len(),
-                                print_req.print_dice_sides_as_their_base
-                            )
-                        );
-                    } else if print_req.cards {
-                        print!("{:}\t", print_as_cards(i, print_req.tidied_list.len()));
-                    }
-                    println!("{}", word);
-                }
+                write_atomically(output, |f| {
+                    // If output path ends in .gz, .xz or .zst, compress the new list
+                    let mut writer = compress_for_path(f, output)?;
+                    write_list(&mut writer, &print_req)?;
+                    // Write the end of any compressed stream before the file is
+                    // renamed into place, so that errors doing so aren't lost
+                    writer.finish()?;
+                    Ok(())
+                })?;
             }
+            // If no output file destination, print resulting list to stdout,
+            // allowing use of > on command line
+            None => match write_list(io::stdout().lock(), &print_req) {
+                // If stdout is piped to a program that stops reading early (like
+                // `head`), that's fine
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+                result => result.map_err(|source| TidyError::Write { path: None, source })?,
+            },
         }
     }
     if !print_req.quiet {
@@ -79,40 +139,167 @@ pub fn print_list(print_req: PrintRequest) {
                 print_req.attributes_as_json,
                 print_req.ignore_after_delimiter,
                 print_req.ignore_before_delimiter,
+                print_req.tabular,
                 print_req.samples,
-            );
+                print_req.seed,
+            )?;
+        }
+        // Card draws aren't part of the JSON attributes, so only show them with the others
+        if let Some(card_options) = print_req.cards
+            && print_req.attributes > 0
+            && !print_req.attributes_as_json
+            && !print_req.tidied_list.is_empty()
+        {
+            eprintln!();
+            display_card_information(print_req.tidied_list.len(), card_options);
         }
     }
+    Ok(())
 }
 
-fn print_list_to_file(
-    tidied_list: &[String],
-    output: PathBuf,
-    cards: bool,
-    dice_sides: Option<u8>,
-    print_dice_sides_as_their_base: bool,
-) {
-    let mut f = File::create(output).expect("Unable to create file");
-    for (i, word) in tidied_list.iter().enumerate() {
-        // If user set a number of dice_sides, we'll add the appropriate
-        // dice roll information, then a tab, then the word.
-        if let Some(dice_sides) = dice_sides {
-            write!(
-                f,
-                "{}\t",
-                print_as_dice(
+/// Make the code (dice rolls or cards) of each word of the list, if the user
+/// asked for them, along with a name for them
+fn make_codes(print_req: &PrintRequest) -> io::Result<Option<(&'static str, Vec<String>)>> {
+    let list_length = print_req.tidied_list.len();
+    if let Some(ref dice_sides) = print_req.dice_sides {
+        let rolls = (0..list_length)
+            .map(|i| {
+                print_dice_rolls(
                     i,
                     dice_sides,
-                    tidied_list.len(),
-                    print_dice_sides_as_their_base
-                ),
+                    list_length,
+                    print_req.print_dice_sides_as_their_base,
+                )
+            })
+            .collect::<Result<Vec<String>, TidyError>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(Some(("dice", rolls)))
+    } else {
+        Ok(print_req.cards.map(|card_options| {
+            (
+                "cards",
+                (0..list_length)
+                    .map(|i| print_card_code(i, list_length, card_options))
+                    .collect(),
             )
-            .expect("Unable to write dice roll to file");
-        } else if cards {
-            write!(f, "{}\t", print_as_cards(i, tidied_list.len()))
-                .expect("Unable to write corresponding card to file");
-        }
+        }))
+    }
+}
+
+/// Write a file by writing to a temporary file next to it, then renaming the
+/// temporary file into place. That way, if Tidy is stopped partway through, the
+/// file is either as it was or completely written, never half written. If anything
+/// goes wrong, the temporary file is removed.
+///
+/// Paths that exist but aren't regular files (like /dev/stdout) can't be renamed
+/// over, so they're written to directly.
+pub fn write_atomically<F>(path: &Path, write: F) -> Result<(), TidyError>
+where
+    F: FnOnce(File) -> io::Result<()>,
+{
+    let write_error = |source| TidyError::Write {
+        path: Some(path.to_path_buf()),
+        source,
+    };
+    if path.exists() && !path.is_file() {
+        return write(File::create(path).map_err(write_error)?).map_err(write_error);
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temporary_path =
+        path.with_file_name(format!(".{}.tidy-{}.tmp", file_name, std::process::id()));
+    let result = File::create(&temporary_path)
+        .and_then(write)
+        // Make sure the new file is on disk before it replaces the old one
+        .and_then(|()| {
+            File::options()
+                .write(true)
+                .open(&temporary_path)?
+                .sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temporary_path, path));
+    if result.is_err() {
+        // There may be no temporary file to remove, which is fine
+        let _ = std::fs::remove_file(&temporary_path);
+    }
+    result.map_err(write_error)
+}
 
-        writeln!(f, "{}", word).expect("Unable to write word to file");
+/// Write the new list, in the format the user asked for, to a file, stdout or
+/// anything else that implements `Write`. Output is buffered, each line is
+/// written as it's made, and the first error (like a full disk) is returned.
+pub fn write_list<W: Write>(writer: W, print_req: &PrintRequest) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    let line_ending = print_req.line_ending.as_str();
+    let codes = make_codes(print_req)?;
+    match print_req.format {
+        OutputFormat::Text => {
+            if let Some(ref header_row) = print_req.header_row {
+                write!(writer, "{}{}", header_row, line_ending)?;
+            }
+            let code_of = |i: usize| codes.as_ref().map(|(_name, codes)| codes[i].as_str());
+            for (i, line) in print_req.tidied_list.iter().enumerate() {
+                let line = match print_req.template {
+                    Some(ref template) => fill_template(template, i, code_of(i), line, print_req),
+                    // If user asked for dice rolls or cards, we'll add the appropriate
+                    // code, then a tab, then the line.
+                    None => match code_of(i) {
+                        Some(code) => format!("{}\t{}", code, line),
+                        None => line.to_string(),
+                    },
+                };
+                write!(writer, "{}{}", line, line_ending)?;
+            }
+        }
+        format => {
+            let table = make_list_table(
+                &print_req.tidied_list,
+                codes,
+                print_req.header_row.as_deref(),
+                print_req.ignore_after_delimiter,
+                print_req.ignore_before_delimiter,
+                print_req.tabular,
+            );
+            write_list_table(&mut writer, &table, format, line_ending)?;
+        }
     }
+    writer.flush()
+}
+
+/// Fill in the template for the line at the given index of the new list
+fn fill_template(
+    template: &LineTemplate,
+    i: usize,
+    code: Option<&str>,
+    line: &str,
+    print_req: &PrintRequest,
+) -> String {
+    let (word, metadata) = split_off_metadata(
+        line,
+        print_req.ignore_after_delimiter,
+        print_req.ignore_before_delimiter,
+        print_req.tabular,
+    );
+    let metadata = match metadata {
+        Some(Metadata::Before { metadata, .. }) | Some(Metadata::After { metadata, .. }) => {
+            Some(metadata)
+        }
+        // Metadata from both sides is joined like the line would be without the word
+        Some(Metadata::Both {
+            before,
+            after,
+            after_delimiter,
+            ..
+        }) => Some(format!("{}{}{}", before, after_delimiter, after)),
+        // Other columns of CSV and TSV files stay in their format
+        Some(Metadata::Tabular { fields, options }) => {
+            let mut other_fields = fields;
+            other_fields.remove(options.word_column);
+            Some(join_record(&other_fields, options.format))
+        }
+        None => None,
+    };
+    template.fill(i, code, line, &word, metadata.as_deref())
 }
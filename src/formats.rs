@@ -0,0 +1,511 @@
+//! Print the new list in formats other than plain text, like JSON or an array
+//! for a Rust, JavaScript or C program, along with any dice or card codes and
+//! metadata columns. Words are escaped for each format, so quotes and
+//! backslashes in words come out right.
+
+use crate::metadata::{Metadata, TabularOptions, parse_record, split_off_metadata};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io::{self, Write};
+
+/// Formats Tidy can print the new list in
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// One line per word, with any code and a tab before it
+    #[default]
+    Text,
+    Json,
+    Csv,
+    /// A Markdown table
+    Markdown,
+    /// A Rust constant
+    Rust,
+    /// A JavaScript module
+    JavaScript,
+    /// A C header file
+    CHeader,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Markdown => write!(f, "markdown"),
+            OutputFormat::Rust => write!(f, "rust"),
+            OutputFormat::JavaScript => write!(f, "js"),
+            OutputFormat::CHeader => write!(f, "c-header"),
+        }
+    }
+}
+
+// Serialized as it'd be typed on the command line, for recipes
+impl Serialize for OutputFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse user's `--format` input (case insensitive).
+/// ```
+/// use tidy::formats::{parse_output_format, OutputFormat};
+/// assert_eq!(parse_output_format("JSON"), Ok(OutputFormat::Json));
+/// assert_eq!(parse_output_format("c-header"), Ok(OutputFormat::CHeader));
+/// assert!(parse_output_format("yaml").is_err());
+/// ```
+pub fn parse_output_format(input: &str) -> Result<OutputFormat, String> {
+    match input.to_lowercase().as_str() {
+        "text" | "txt" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        "csv" => Ok(OutputFormat::Csv),
+        "markdown" | "md" => Ok(OutputFormat::Markdown),
+        "rust" | "rs" => Ok(OutputFormat::Rust),
+        "js" | "javascript" => Ok(OutputFormat::JavaScript),
+        "c-header" | "c" | "h" => Ok(OutputFormat::CHeader),
+        _ => Err(format!(
+            "Unknown format {:?}. Options are text, json, csv, markdown, rust, js and c-header",
+            input
+        )),
+    }
+}
+
+/// The new list as a table: one row per word, with columns for the word's code
+/// (dice rolls or cards), the word itself and any metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Split the lines of the new list into a table. `codes` is the name of the codes'
+/// column (like "dice") and the code of each line, if any.
+///
+/// If metadata is being ignored, it gets its own column (or two, one for each side
+/// of the word, if ignoring metadata on both sides). CSV and TSV files instead
+/// keep each of their other columns, named by the header row if there is one.
+pub fn make_list_table(
+    list: &[String],
+    codes: Option<(&str, Vec<String>)>,
+    header_row: Option<&str>,
+    ignore_after_delimiter: Option<char>,
+    ignore_before_delimiter: Option<char>,
+    tabular: Option<TabularOptions>,
+) -> ListTable {
+    let mut columns = vec![];
+    if let Some((codes_name, _codes)) = &codes {
+        columns.push(codes_name.to_string());
+    }
+    columns.push("word".to_string());
+    if let Some(options) = tabular {
+        let header = header_row
+            .map(|header_row| parse_record(header_row, options.format))
+            .unwrap_or_default();
+        // Rows may be ragged, so find the widest one
+        let number_of_fields = list
+            .iter()
+            .map(|line| parse_record(line, options.format).len())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default();
+        columns.extend(
+            (0..number_of_fields)
+                .filter(|column| *column != options.word_column)
+                .map(|column| match header.get(column) {
+                    Some(name) => name.to_string(),
+                    None => format!("column {}", column + 1),
+                }),
+        );
+    } else if ignore_after_delimiter.is_some() && ignore_before_delimiter.is_some() {
+        columns.push("metadata before".to_string());
+        columns.push("metadata after".to_string());
+    } else if ignore_after_delimiter.is_some() || ignore_before_delimiter.is_some() {
+        columns.push("metadata".to_string());
+    }
+    let has_metadata_on_both_sides =
+        ignore_after_delimiter.is_some() && ignore_before_delimiter.is_some();
+
+    let rows = list
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let mut row = vec![];
+            if let Some((_codes_name, codes)) = &codes {
+                row.push(codes[i].to_string());
+            }
+            let (word, metadata) = split_off_metadata(
+                line,
+                ignore_after_delimiter,
+                ignore_before_delimiter,
+                tabular,
+            );
+            row.push(word);
+            match metadata {
+                Some(Metadata::Both { before, after, .. }) => {
+                    row.push(before);
+                    row.push(after);
+                }
+                // If a line is missing the metadata before the word, leave
+                // that column empty
+                Some(Metadata::After { metadata, .. }) if has_metadata_on_both_sides => {
+                    row.push(String::new());
+                    row.push(metadata);
+                }
+                Some(Metadata::Before { metadata, .. })
+                | Some(Metadata::After { metadata, .. }) => row.push(metadata),
+                Some(Metadata::Tabular { fields, options }) => row.extend(
+                    fields
+                        .into_iter()
+                        .enumerate()
+                        .filter(|(column, _field)| *column != options.word_column)
+                        .map(|(_column, field)| field),
+                ),
+                None => {}
+            }
+            // Fill in any missing fields, so every row has every column
+            row.resize(columns.len(), String::new());
+            row
+        })
+        .collect();
+    ListTable { columns, rows }
+}
+
+/// Write the table to the given writer in the given format, ending each line
+/// with `line_ending`. If the table only has the words, source-code formats
+/// make a plain array of strings.
+///
+/// For `Text`, this just joins each row's columns with tabs. `file_writer` prints
+/// plain text lists itself, so that metadata stays where it was on each line.
+pub fn write_list_table<W: Write>(
+    writer: &mut W,
+    table: &ListTable,
+    format: OutputFormat,
+    line_ending: &str,
+) -> io::Result<()> {
+    let only_words = table.columns.len() == 1;
+    let length = table.rows.len();
+    let lines: Box<dyn Iterator<Item = String> + '_> = match format {
+        OutputFormat::Text => Box::new(table.rows.iter().map(|row| row.join("\t"))),
+        // Fields can have line breaks in them, so let the csv crate write whole records
+        OutputFormat::Csv => {
+            let terminator = match line_ending {
+                "\n" => csv::Terminator::Any(b'\n'),
+                _ => csv::Terminator::CRLF,
+            };
+            let mut csv_writer = csv::WriterBuilder::new()
+                .terminator(terminator)
+                .from_writer(writer);
+            for record in std::iter::once(&table.columns).chain(table.rows.iter()) {
+                csv_writer.write_record(record)?;
+            }
+            return csv_writer.flush();
+        }
+        OutputFormat::Markdown => Box::new(
+            [
+                markdown_row(&table.columns),
+                markdown_row(&vec!["---".to_string(); table.columns.len()]),
+            ]
+            .into_iter()
+            .chain(table.rows.iter().map(|row| markdown_row(row))),
+        ),
+        OutputFormat::Json => Box::new(
+            std::iter::once("[".to_string())
+                .chain(with_commas(table.rows.iter().map(move |row| {
+                    format!("  {}", json_row(&table.columns, row, only_words))
+                })))
+                .chain(std::iter::once("]".to_string())),
+        ),
+        OutputFormat::JavaScript => Box::new(
+            std::iter::once("export const words = [".to_string())
+                .chain(with_commas(table.rows.iter().map(move |row| {
+                    format!("  {}", json_row(&table.columns, row, only_words))
+                })))
+                .chain(std::iter::once("];".to_string())),
+        ),
+        OutputFormat::Rust => {
+            let mut header = vec![];
+            let row_type = if only_words {
+                "&str".to_string()
+            } else {
+                header.push(format!("// ({})", table.columns.join(", ")));
+                format!("({})", vec!["&str"; table.columns.len()].join(", "))
+            };
+            header.push(format!("pub const WORDS: [{}; {}] = [", row_type, length));
+            Box::new(
+                header
+                    .into_iter()
+                    .chain(table.rows.iter().map(move |row| {
+                        // Rust's Debug formatting of a str is a valid Rust string literal
+                        let fields: Vec<String> =
+                            row.iter().map(|field| format!("{:?}", field)).collect();
+                        if only_words {
+                            format!("    {},", fields[0])
+                        } else {
+                            format!("    ({}),", fields.join(", "))
+                        }
+                    }))
+                    .chain(std::iter::once("];".to_string())),
+            )
+        }
+        OutputFormat::CHeader => {
+            let mut header = vec![
+                "#ifndef WORDS_H".to_string(),
+                "#define WORDS_H".to_string(),
+                String::new(),
+                format!("#define WORDS_LENGTH {}", length),
+            ];
+            // C (before C23) doesn't allow empty arrays, so an empty list gets an
+            // array of one null entry, while WORDS_LENGTH stays 0
+            let array_length = if length == 0 { "1" } else { "WORDS_LENGTH" };
+            if only_words {
+                header.push(format!(
+                    "static const char *const words[{}] = {{",
+                    array_length
+                ));
+            } else {
+                header.push(format!("#define WORDS_COLUMNS {}", table.columns.len()));
+                // Don't let a column name end the comment early
+                header.push(format!(
+                    "/* {} */",
+                    table.columns.join(", ").replace("*/", "* /")
+                ));
+                header.push(format!(
+                    "static const char *const words[{}][WORDS_COLUMNS] = {{",
+                    array_length
+                ));
+            }
+            if length == 0 {
+                header.push(if only_words { "    0" } else { "    {0}" }.to_string());
+            }
+            Box::new(
+                header
+                    .into_iter()
+                    .chain(with_commas(table.rows.iter().map(move |row| {
+                        let fields: Vec<String> = row.iter().map(|field| c_string(field)).collect();
+                        if only_words {
+                            format!("    {}", fields[0])
+                        } else {
+                            format!("    {{{}}}", fields.join(", "))
+                        }
+                    })))
+                    .chain(["};".to_string(), String::new(), "#endif".to_string()]),
+            )
+        }
+    };
+    // Write each line as it's made, rather than making them all first
+    for line in lines {
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(line_ending.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Add a comma to the end of every line but the last, for formats (like JSON)
+/// that don't allow trailing commas
+fn with_commas(lines: impl ExactSizeIterator<Item = String>) -> impl Iterator<Item = String> {
+    let last = lines.len().saturating_sub(1);
+    lines
+        .enumerate()
+        .map(move |(i, line)| if i < last { line + "," } else { line })
+}
+
+/// A row of a Markdown table, with pipes and backslashes escaped
+fn markdown_row(fields: &[String]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| field.replace('\\', "\\\\").replace('|', "\\|"))
+        .collect();
+    format!("| {} |", fields.join(" | "))
+}
+
+/// A row as a JSON string or, if there's more than one column, a JSON object.
+/// Keys stay in column order.
+fn json_row(columns: &[String], row: &[String], only_words: bool) -> String {
+    // Serializing a string can't fail
+    let json_string = |s: &str| serde_json::to_string(s).unwrap_or_default();
+    if only_words {
+        json_string(&row[0])
+    } else {
+        let fields: Vec<String> = columns
+            .iter()
+            .zip(row)
+            .map(|(column, field)| format!("{}: {}", json_string(column), json_string(field)))
+            .collect();
+        format!("{{{}}}", fields.join(", "))
+    }
+}
+
+/// A C string literal. Bytes outside of printable ASCII (including those of
+/// UTF-8 characters) are written as octal escapes, which, unlike hex escapes,
+/// can't run into the characters after them.
+/// ```
+/// use tidy::formats::c_string;
+/// assert_eq!(c_string("say \"hi\"\\"), "\"say \\\"hi\\\"\\\\\"");
+/// assert_eq!(c_string("café"), "\"caf\\303\\251\"");
+/// ```
+pub fn c_string(s: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in s.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            // Avoid accidental trigraphs, like ??/
+            b'?' => literal.push_str("\\?"),
+            0x20..=0x7e => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// One piece of a line template
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatePart {
+    Literal(String),
+    /// The word's place on the list, starting at 1
+    Index,
+    /// The word's dice rolls or cards
+    Code,
+    /// The word, without any metadata
+    Word,
+    /// Any metadata split off of the word (see --ignore-after and --ignore-before).
+    /// Metadata from both sides of the word is joined by the delimiter after the word.
+    Metadata,
+    /// The whole line, word and metadata
+    Line,
+}
+
+/// A template for each line of a plain text list, like `{index}:{dice} {word}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineTemplate {
+    pub parts: Vec<TemplatePart>,
+}
+
+impl LineTemplate {
+    /// Whether the template has a dice roll or card code in it
+    pub fn uses_codes(&self) -> bool {
+        self.parts.contains(&TemplatePart::Code)
+    }
+
+    /// Fill in the template for one line of the list. `index` is zero-indexed,
+    /// though `{index}` starts at 1.
+    pub fn fill(
+        &self,
+        index: usize,
+        code: Option<&str>,
+        line: &str,
+        word: &str,
+        metadata: Option<&str>,
+    ) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Literal(literal) => literal.to_string(),
+                TemplatePart::Index => (index + 1).to_string(),
+                TemplatePart::Code => code.unwrap_or_default().to_string(),
+                TemplatePart::Word => word.to_string(),
+                TemplatePart::Metadata => metadata.unwrap_or_default().to_string(),
+                TemplatePart::Line => line.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for LineTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => {
+                    write!(f, "{}", literal.replace('{', "{{").replace('}', "}}"))?
+                }
+                TemplatePart::Index => write!(f, "{{index}}")?,
+                TemplatePart::Code => write!(f, "{{code}}")?,
+                TemplatePart::Word => write!(f, "{{word}}")?,
+                TemplatePart::Metadata => write!(f, "{{metadata}}")?,
+                TemplatePart::Line => write!(f, "{{line}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Serialized as it'd be typed on the command line, for recipes
+impl Serialize for LineTemplate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse user's `--template` input. Placeholders are `{index}` (starting at 1),
+/// `{dice}` or `{cards}` (or `{code}`, for either), `{word}`, `{metadata}` and
+/// `{line}`. Use `{{` and `}}` for literal braces.
+/// ```
+/// use tidy::formats::{parse_line_template, TemplatePart};
+/// let template = parse_line_template("{index}:{dice} {word}").unwrap();
+/// assert_eq!(template.fill(0, Some("11111"), "abacus", "abacus", None), "1:11111 abacus");
+/// assert_eq!(parse_line_template("{{{word}}}").unwrap().fill(0, None, "zoo", "zoo", None), "{zoo}");
+/// assert_eq!(template.to_string(), "{index}:{code} {word}");
+/// assert!(parse_line_template("{score}").is_err());
+/// assert!(parse_line_template("{word").is_err());
+/// ```
+pub fn parse_line_template(input: &str) -> Result<LineTemplate, String> {
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut chars = input.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => name.push(ch),
+                        None => {
+                            return Err(format!(
+                                "Template {:?} has a {{ without a matching }}. Use {{{{ for a literal {{",
+                                input
+                            ));
+                        }
+                    }
+                }
+                let part = match name.as_str() {
+                    "index" => TemplatePart::Index,
+                    "dice" | "cards" | "code" => TemplatePart::Code,
+                    "word" => TemplatePart::Word,
+                    "metadata" => TemplatePart::Metadata,
+                    "line" => TemplatePart::Line,
+                    _ => {
+                        return Err(format!(
+                            "Unknown placeholder {{{}}} in template. Options are {{index}}, {{dice}}, {{cards}}, {{code}}, {{word}}, {{metadata}} and {{line}}",
+                            name
+                        ));
+                    }
+                };
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(part);
+            }
+            '}' => {
+                return Err(format!(
+                    "Template {:?} has a }} without a matching {{. Use }}}} for a literal }}",
+                    input
+                ));
+            }
+            _ => literal.push(ch),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+    Ok(LineTemplate { parts })
+}
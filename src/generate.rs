@@ -0,0 +1,165 @@
+//! Generate passphrases from a word list, using a cryptographically secure
+//! random number generator. Unlike the word samples (`--samples`), these are
+//! meant to be used.
+
+use crate::diff::split_code;
+use crate::display_information::calc_entropy_per_word;
+use crate::error::TidyError;
+use rand::{CryptoRng, Rng};
+use std::collections::HashSet;
+use std::fmt;
+
+/// How to capitalize the words of a passphrase
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Capitalization {
+    /// Leave words as they are on the list
+    #[default]
+    AsIs,
+    Lowercase,
+    Uppercase,
+    /// Capitalize the first letter of each word
+    FirstLetter,
+}
+
+impl fmt::Display for Capitalization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Capitalization::AsIs => write!(f, "as-is"),
+            Capitalization::Lowercase => write!(f, "lower"),
+            Capitalization::Uppercase => write!(f, "upper"),
+            Capitalization::FirstLetter => write!(f, "first"),
+        }
+    }
+}
+
+/// Parse user's `--capitalize` input.
+/// ```
+/// use tidy::generate::{parse_capitalization, Capitalization};
+/// assert_eq!(parse_capitalization("first"), Ok(Capitalization::FirstLetter));
+/// assert_eq!(parse_capitalization("UPPER"), Ok(Capitalization::Uppercase));
+/// assert!(parse_capitalization("random").is_err());
+/// ```
+pub fn parse_capitalization(input: &str) -> Result<Capitalization, String> {
+    match input.to_lowercase().as_str() {
+        "as-is" | "none" => Ok(Capitalization::AsIs),
+        "lower" | "lowercase" => Ok(Capitalization::Lowercase),
+        "upper" | "uppercase" => Ok(Capitalization::Uppercase),
+        "first" | "title" => Ok(Capitalization::FirstLetter),
+        _ => Err(format!(
+            "Unknown capitalization {:?}. Options are as-is, lower, upper and first",
+            input
+        )),
+    }
+}
+
+/// Capitalize a word
+/// ```
+/// use tidy::generate::{capitalize, Capitalization};
+/// assert_eq!(capitalize("éclair", Capitalization::FirstLetter), "Éclair");
+/// assert_eq!(capitalize("Zebra", Capitalization::Lowercase), "zebra");
+/// ```
+pub fn capitalize(word: &str, capitalization: Capitalization) -> String {
+    match capitalization {
+        Capitalization::AsIs => word.to_string(),
+        Capitalization::Lowercase => word.to_lowercase(),
+        Capitalization::Uppercase => word.to_uppercase(),
+        Capitalization::FirstLetter => {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+/// Options for making passphrases
+#[derive(Debug, Clone)]
+pub struct PassphraseOptions {
+    pub number_of_words: usize,
+    /// Put between words (and before any digits)
+    pub separator: String,
+    pub capitalization: Capitalization,
+    /// Number of random digits to add to the end of the passphrase
+    pub digits: usize,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        PassphraseOptions {
+            number_of_words: 6,
+            separator: " ".to_string(),
+            capitalization: Capitalization::AsIs,
+            digits: 0,
+        }
+    }
+}
+
+/// Make the list of words to choose from. If every line starts with a dice roll
+/// (or other code), like `11111 abacus`, the codes are removed. Words are then
+/// capitalized, and any duplicates (like "Polish" and "polish", when capitalized
+/// the same way) are removed, so that every word is equally likely and the
+/// entropy we report is exact.
+///
+/// Returns an error if there are no words on the list.
+pub fn make_generator_list(
+    lines: &[String],
+    capitalization: Capitalization,
+) -> Result<Vec<String>, TidyError> {
+    let lines: Vec<&str> = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    let has_codes = lines.iter().all(|line| split_code(line).is_some());
+    let mut seen = HashSet::new();
+    let list: Vec<String> = lines
+        .iter()
+        .map(|line| match split_code(line) {
+            Some((_code, word)) if has_codes => word,
+            _ => line,
+        })
+        .map(|word| capitalize(word, capitalization))
+        .filter(|word| seen.insert(word.clone()))
+        .collect();
+    if list.is_empty() {
+        return Err(TidyError::EmptyList {
+            operation: "make a passphrase out".to_string(),
+        });
+    }
+    Ok(list)
+}
+
+/// Make one passphrase from a list made by `make_generator_list`. Each word (and
+/// digit) is chosen independently, with every word on the list equally likely:
+/// `random_range` rejects the random numbers that would favor some words over others.
+pub fn generate_passphrase<R: Rng + CryptoRng>(
+    list: &[String],
+    options: &PassphraseOptions,
+    rng: &mut R,
+) -> String {
+    let mut parts: Vec<String> = (0..options.number_of_words)
+        .map(|_n| list[rng.random_range(0..list.len())].clone())
+        .collect();
+    if options.digits > 0 {
+        parts.push(
+            (0..options.digits)
+                .map(|_n| rng.random_range(0..10).to_string())
+                .collect(),
+        );
+    }
+    parts.join(&options.separator)
+}
+
+/// Calculate the entropy, in bits, of each passphrase made from a list of a given
+/// length. This is exact as long as the words can't be combined in two ways to make
+/// the same passphrase, which a separator (or a uniquely decodable list) ensures.
+/// ```
+/// use tidy::generate::{calc_passphrase_entropy, PassphraseOptions};
+/// let options = PassphraseOptions { digits: 2, ..Default::default() };
+/// assert_eq!(calc_passphrase_entropy(7_776, &options), 6.0 * 7_776_f64.log2() + 2.0 * 10_f64.log2());
+/// ```
+pub fn calc_passphrase_entropy(list_length: usize, options: &PassphraseOptions) -> f64 {
+    options.number_of_words as f64 * calc_entropy_per_word(list_length)
+        + options.digits as f64 * 10_f64.log2()
+}
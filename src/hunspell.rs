@@ -0,0 +1,346 @@
+//! Parse Hunspell dictionaries (a `.dic` file of stems plus a `.aff` file of
+//! affix rules) into plain lists of words.
+//!
+//! This is not a full implementation of Hunspell's (many!) features. It
+//! understands the common `FLAG` types, flag aliases (`AF`), prefix and suffix
+//! rules (`PFX` and `SFX`) including cross products, and the `NEEDAFFIX` and
+//! `FORBIDDENWORD` flags. Continuation flags on affixes ("twofold" affixes)
+//! and compounding rules are ignored.
+//! See <https://man.archlinux.org/man/hunspell.5.en> for the file formats.
+
+use std::collections::HashMap;
+
+/// How the user wants Tidy to turn a Hunspell dictionary into words
+#[derive(Default, Debug, Clone)]
+pub struct HunspellOptions {
+    /// Only emit the stems listed in the `.dic` file, without applying any affix rules
+    pub stems_only: bool,
+    /// If given, only apply affix rules with these flags
+    pub flags: Option<Vec<String>>,
+}
+
+/// The ways a Hunspell affix file can represent flags
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum FlagType {
+    /// One character per flag (the default, also covers `FLAG UTF-8`)
+    #[default]
+    Single,
+    /// Two characters per flag (`FLAG long`)
+    Long,
+    /// Comma-separated numbers (`FLAG num`)
+    Numeric,
+}
+
+/// Split a string of flags (e.g. the "AB" in "walk/AB") into individual flags.
+/// ```
+/// use tidy::hunspell::{parse_flags, FlagType};
+/// assert_eq!(parse_flags("AB", FlagType::Single), vec!["A", "B"]);
+/// assert_eq!(parse_flags("AaBb", FlagType::Long), vec!["Aa", "Bb"]);
+/// assert_eq!(parse_flags("1,23", FlagType::Numeric), vec!["1", "23"]);
+/// ```
+pub fn parse_flags(flags: &str, flag_type: FlagType) -> Vec<String> {
+    match flag_type {
+        FlagType::Single => flags.chars().map(|c| c.to_string()).collect(),
+        FlagType::Long => flags
+            .chars()
+            .collect::<Vec<char>>()
+            .chunks(2)
+            .map(|pair| pair.iter().collect())
+            .collect(),
+        FlagType::Numeric => flags
+            .split(',')
+            .map(|flag| flag.trim().to_string())
+            .filter(|flag| !flag.is_empty())
+            .collect(),
+    }
+}
+
+/// One character of an affix rule's condition
+#[derive(Debug, Clone, PartialEq)]
+enum ConditionCharacter {
+    Any,
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+    Exactly(char),
+}
+
+impl ConditionCharacter {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionCharacter::Any => true,
+            ConditionCharacter::OneOf(chars) => chars.contains(&c),
+            ConditionCharacter::NoneOf(chars) => !chars.contains(&c),
+            ConditionCharacter::Exactly(expected) => *expected == c,
+        }
+    }
+}
+
+/// Parse a condition like `[^aeiou]y` into a sequence of characters to match
+fn parse_condition(condition: &str) -> Vec<ConditionCharacter> {
+    let mut parsed = vec![];
+    if condition == "." {
+        return parsed;
+    }
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parsed.push(ConditionCharacter::Any),
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|c| *c != ']').collect();
+                if set.first() == Some(&'^') {
+                    set.remove(0);
+                    parsed.push(ConditionCharacter::NoneOf(set));
+                } else {
+                    parsed.push(ConditionCharacter::OneOf(set));
+                }
+            }
+            _ => parsed.push(ConditionCharacter::Exactly(c)),
+        }
+    }
+    parsed
+}
+
+#[derive(Debug, Clone)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<ConditionCharacter>,
+}
+
+#[derive(Debug, Clone)]
+struct AffixClass {
+    is_prefix: bool,
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+impl AffixClass {
+    /// Apply every rule of this class that fits the given word
+    fn apply(&self, word: &str) -> Vec<String> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut new_words = vec![];
+        for rule in &self.rules {
+            let condition_length = rule.condition.len();
+            if condition_length > chars.len() {
+                continue;
+            }
+            if self.is_prefix {
+                if word.starts_with(&rule.strip)
+                    && rule
+                        .condition
+                        .iter()
+                        .zip(chars.iter())
+                        .all(|(condition, c)| condition.matches(*c))
+                {
+                    new_words.push(rule.add.to_owned() + &word[rule.strip.len()..]);
+                }
+            } else if word.ends_with(&rule.strip)
+                && rule
+                    .condition
+                    .iter()
+                    .zip(chars[chars.len() - condition_length..].iter())
+                    .all(|(condition, c)| condition.matches(*c))
+            {
+                new_words.push(word[..word.len() - rule.strip.len()].to_owned() + &rule.add);
+            }
+        }
+        new_words
+    }
+}
+
+/// The parts of a Hunspell affix (`.aff`) file that Tidy uses
+#[derive(Default, Debug, Clone)]
+pub struct AffixFile {
+    /// The character encoding declared by the `SET` line, if any
+    pub encoding: Option<String>,
+    pub flag_type: FlagType,
+    flag_aliases: Vec<String>,
+    need_affix_flag: Option<String>,
+    forbidden_word_flag: Option<String>,
+    classes: HashMap<String, AffixClass>,
+}
+
+/// Parse the lines of a Hunspell affix (`.aff`) file
+pub fn parse_affix_file(lines: &[String]) -> AffixFile {
+    let mut affix_file = AffixFile::default();
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["SET", encoding, ..] => affix_file.encoding = Some(encoding.to_string()),
+            ["FLAG", flag_type, ..] => {
+                affix_file.flag_type = match *flag_type {
+                    "long" => FlagType::Long,
+                    "num" => FlagType::Numeric,
+                    _ => FlagType::Single,
+                }
+            }
+            // The first AF line gives the number of aliases, which we don't need
+            ["AF", aliased_flags, ..] if aliased_flags.parse::<usize>().is_err() => {
+                affix_file.flag_aliases.push(aliased_flags.to_string())
+            }
+            ["NEEDAFFIX", flag, ..] => affix_file.need_affix_flag = Some(flag.to_string()),
+            ["FORBIDDENWORD", flag, ..] => affix_file.forbidden_word_flag = Some(flag.to_string()),
+            // Header line of a class of affix rules, like "SFX D Y 4"
+            [kind @ ("PFX" | "SFX"), flag, cross_product, count]
+                if count.parse::<usize>().is_ok() =>
+            {
+                affix_file.classes.insert(
+                    flag.to_string(),
+                    AffixClass {
+                        is_prefix: *kind == "PFX",
+                        cross_product: *cross_product == "Y",
+                        rules: vec![],
+                    },
+                );
+            }
+            // An affix rule, like "SFX D y ied [^aeiou]y"
+            ["PFX" | "SFX", flag, strip, add, rest @ ..] => {
+                let class = match affix_file.classes.get_mut(*flag) {
+                    Some(class) => class,
+                    None => {
+                        eprintln!("Found affix rule before its header: {:?}", line);
+                        continue;
+                    }
+                };
+                // We don't handle continuation flags (e.g. "ed/XY"), so drop them
+                let add = add.split('/').next().unwrap_or_default();
+                class.rules.push(AffixRule {
+                    strip: if *strip == "0" {
+                        String::new()
+                    } else {
+                        strip.to_string()
+                    },
+                    add: if add == "0" {
+                        String::new()
+                    } else {
+                        add.to_string()
+                    },
+                    condition: parse_condition(rest.first().unwrap_or(&".")),
+                });
+            }
+            _ => {}
+        }
+    }
+    affix_file
+}
+
+impl AffixFile {
+    /// Split a line of a `.dic` file into its stem and its flags. Comments and
+    /// blank lines give `None`. A stem can contain a slash if it's escaped, as `\/`.
+    pub fn parse_dictionary_line(&self, line: &str) -> Option<(String, Vec<String>)> {
+        // Anything after a tab is morphological information, which we don't need
+        let entry = line.split('\t').next().unwrap_or_default().trim();
+        if entry.is_empty() || entry.starts_with('#') {
+            return None;
+        }
+        // Some dictionaries also use a space before the morphological information
+        let entry = entry.split(' ').next().unwrap_or_default();
+        let (stem, flags) = split_stem_and_flags(entry);
+        match flags {
+            Some(flags) => {
+                let flags = match flags.parse::<usize>() {
+                    // If the affix file defines flag aliases, a number refers to one of them
+                    Ok(alias) if !self.flag_aliases.is_empty() => {
+                        match self.flag_aliases.get(alias.wrapping_sub(1)) {
+                            Some(aliased_flags) => parse_flags(aliased_flags, self.flag_type),
+                            None => {
+                                eprintln!("Unknown flag alias {} on line {:?}", alias, line);
+                                vec![]
+                            }
+                        }
+                    }
+                    _ => parse_flags(flags, self.flag_type),
+                };
+                Some((stem, flags))
+            }
+            None => Some((stem, vec![])),
+        }
+    }
+
+    /// Produce all the word forms of one stem, given its flags
+    pub fn expand_stem(
+        &self,
+        stem: &str,
+        flags: &[String],
+        options: &HunspellOptions,
+    ) -> Vec<String> {
+        if let Some(ref forbidden_word_flag) = self.forbidden_word_flag
+            && flags.contains(forbidden_word_flag)
+        {
+            return vec![];
+        }
+        let mut words = vec![];
+        let needs_affix = match self.need_affix_flag {
+            Some(ref need_affix_flag) => flags.contains(need_affix_flag),
+            None => false,
+        };
+        if !needs_affix || options.stems_only {
+            words.push(stem.to_string());
+        }
+        if options.stems_only {
+            return words;
+        }
+        // Only use the affix classes this stem has flags for (and that the user allowed)
+        let classes: Vec<&AffixClass> = flags
+            .iter()
+            .filter(|flag| match options.flags {
+                Some(ref allowed_flags) => allowed_flags.contains(flag),
+                None => true,
+            })
+            .filter_map(|flag| self.classes.get(flag))
+            .collect();
+        let mut cross_product_suffixed_words = vec![];
+        for class in classes.iter().filter(|class| !class.is_prefix) {
+            for word in class.apply(stem) {
+                if class.cross_product {
+                    cross_product_suffixed_words.push(word.clone());
+                }
+                words.push(word);
+            }
+        }
+        for class in classes.iter().filter(|class| class.is_prefix) {
+            words.append(&mut class.apply(stem));
+            // Prefixes and suffixes that both allow cross products can be combined
+            if class.cross_product {
+                for suffixed_word in &cross_product_suffixed_words {
+                    words.append(&mut class.apply(suffixed_word));
+                }
+            }
+        }
+        words
+    }
+
+    /// Turn all lines of a `.dic` file into a list of words
+    pub fn expand_dictionary(&self, lines: &[String], options: &HunspellOptions) -> Vec<String> {
+        // The first line of a `.dic` file is the (approximate) number of entries
+        let lines = match lines.first() {
+            Some(first_line) if first_line.trim().parse::<usize>().is_ok() => &lines[1..],
+            _ => lines,
+        };
+        let mut words = vec![];
+        for line in lines {
+            if let Some((stem, flags)) = self.parse_dictionary_line(line) {
+                words.append(&mut self.expand_stem(&stem, &flags, options));
+            }
+        }
+        words
+    }
+}
+
+/// Split a dictionary entry at its first unescaped slash, unescaping any
+/// escaped slashes (`\/`) in the stem
+fn split_stem_and_flags(entry: &str) -> (String, Option<&str>) {
+    let mut stem = String::new();
+    let mut chars = entry.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars.peek().map(|&(_, next)| next) == Some('/') => {
+                stem.push('/');
+                chars.next();
+            }
+            '/' => return (stem, Some(&entry[i + 1..])),
+            _ => stem.push(c),
+        }
+    }
+    (stem, None)
+}
@@ -1,8 +1,8 @@
-pub fn validate_dice_sides(dice_sides: Option<u8>) -> Result<(), &'static str> {
-    if let Some(dice_sides) = dice_sides {
-        if !(2 <= dice_sides && dice_sides <= 36) {
-            return Err("Error: Specified number of dice sides must be between 2 and 36.");
-        }
+pub fn validate_dice_sides(dice_sides: Option<&[u8]>) -> Result<(), &'static str> {
+    if let Some(dice_sides) = dice_sides
+        && !dice_sides.iter().all(|sides| (2..=36).contains(sides))
+    {
+        return Err("Error: Specified number of dice sides must be between 2 and 36.");
     }
     Ok(())
 }
@@ -27,46 +27,94 @@ pub fn validate_list_truncation_options(
     }
 }
 
+use std::path::PathBuf;
+use tidy::file_readers::is_stdin;
+/// Standard input can only be read once, so "-" can only be given as
+/// one of the inputted files, whether that's a word list, a reject list,
+/// an approved list or a homophones list.
+pub fn validate_stdin_usage(all_inputted_files: &[&PathBuf]) -> Result<(), &'static str> {
+    if all_inputted_files
+        .iter()
+        .filter(|file| is_stdin(file))
+        .count()
+        > 1
+    {
+        Err("Error: Can only read from stdin (\"-\") once. Please only give \"-\" as one input.")
+    } else {
+        Ok(())
+    }
+}
+
+/// Blending weights need to match up with the inputted word lists, and a
+/// weight of 0 would mean never taking any words from that list.
+pub fn validate_weights(weights: &[usize], number_of_word_lists: usize) -> Result<(), String> {
+    if weights.len() != number_of_word_lists {
+        Err(format!(
+            "Error: Given {} weight(s) for {} inputted word list(s). Please give one weight per list.",
+            weights.len(),
+            number_of_word_lists
+        ))
+    } else if weights.contains(&0) {
+        Err("Error: Weights must be at least 1.".to_string())
+    } else {
+        Ok(())
+    }
+}
+
 use crate::TidyRequest;
+/// Deleting characters before or after a delimiter can't be used while ignoring
+/// metadata, as the two delimiters are too easily mixed up. (Other word modifications
+/// are fine, since they only change the word. If words end up the same, the merge
+/// policy decides what happens to their metadata.)
+fn uses_options_incompatible_with_metadata(this_tidy_request: &TidyRequest) -> bool {
+    this_tidy_request
+        .should_delete_before_first_delimiter
+        .is_some()
+        || this_tidy_request
+            .should_delete_after_first_delimiter
+            .is_some()
+}
+
 pub fn validate_and_parse_ignore_options(
     this_tidy_request: &TidyRequest,
-    dice_sides: Option<u8>,
-    print_dice_sides_as_their_base: bool,
 ) -> Result<(Option<char>, Option<char>), &'static str> {
-    // Warn about the (many!) current limitations of the 'ignore' options
+    let incompatible_options_used = uses_options_incompatible_with_metadata(this_tidy_request);
+    // CSV and TSV files keep their other columns as metadata, so the
+    // same limitations apply.
+    if this_tidy_request.tabular.is_some() {
+        return if this_tidy_request.ignore_after_delimiter.is_some()
+            || this_tidy_request.ignore_before_delimiter.is_some()
+        {
+            Err("Can't use --ignore-after or --ignore-before options with CSV or TSV files.")
+        } else if incompatible_options_used {
+            Err(
+                "--csv and --tsv options do not work with one of the other options you selected. Please change options. Exiting",
+            )
+        } else {
+            Ok((None, None))
+        };
+    }
+    // Only --delete-before and --delete-after conflict with ignoring metadata
+    // (see `uses_options_incompatible_with_metadata`)
     match (
         this_tidy_request.ignore_after_delimiter,
         this_tidy_request.ignore_before_delimiter,
     ) {
-        // If given both a from_delimiter and through_delimiter, error out nicely.
-        (Some(_after_delimiter), Some(_before_delimiter)) => {
-            let err_message = "Can't ignore metadata on both sides.";
-            Err(err_message)
+        // Both delimiters given, so there's metadata on both sides of each word
+        (Some(after_delimiter), Some(before_delimiter)) => {
+            if incompatible_options_used {
+                let err_message = "--ignore-after and --ignore-before options do not work with one of the other options you selected. Please change options. Exiting";
+                Err(err_message)
+            } else {
+                Ok((Some(after_delimiter), Some(before_delimiter)))
+            }
         }
         // No ignore delimiters given, so just return None to both
         // variables.
         (None, None) => Ok((None, None)),
         // A after_delimiter given, but not a before_delimiter
         (Some(after_delimiter), None) => {
-            if this_tidy_request.to_lowercase
-                || this_tidy_request.should_straighten_quotes
-                || this_tidy_request.should_remove_prefix_words
-                || this_tidy_request.should_remove_suffix_words
-                || this_tidy_request.should_schlinkert_prune
-                || this_tidy_request.should_delete_nonalphanumeric
-                || this_tidy_request.should_delete_integers
-                || this_tidy_request
-                    .should_delete_before_first_delimiter
-                    .is_some()
-                || this_tidy_request
-                    .should_delete_after_first_delimiter
-                    .is_some()
-                || this_tidy_request.minimum_edit_distance.is_some()
-                || this_tidy_request.maximum_shared_prefix_length.is_some()
-                || this_tidy_request.homophones_list.is_some()
-                || dice_sides.is_some()
-                || print_dice_sides_as_their_base
-            {
+            if incompatible_options_used {
                 let err_message = "--ignore-after option does not work with one of the other options you selected. Please change options. Exiting";
                 Err(err_message)
             } else {
@@ -75,25 +123,7 @@ pub fn validate_and_parse_ignore_options(
         }
         // No after_delimiter given, but a before_delimiter has been given
         (None, Some(before_delimiter)) => {
-            if this_tidy_request.to_lowercase
-                || this_tidy_request.should_straighten_quotes
-                || this_tidy_request.should_remove_prefix_words
-                || this_tidy_request.should_remove_suffix_words
-                || this_tidy_request.should_schlinkert_prune
-                || this_tidy_request.should_delete_nonalphanumeric
-                || this_tidy_request.should_delete_integers
-                || this_tidy_request
-                    .should_delete_before_first_delimiter
-                    .is_some()
-                || this_tidy_request
-                    .should_delete_after_first_delimiter
-                    .is_some()
-                || this_tidy_request.minimum_edit_distance.is_some()
-                || this_tidy_request.maximum_shared_prefix_length.is_some()
-                || this_tidy_request.homophones_list.is_some()
-                || dice_sides.is_some()
-                || print_dice_sides_as_their_base
-            {
+            if incompatible_options_used {
                 let err_message = "--ignore-before option does not work with one of the other options you selected. Please change options. Exiting";
                 Err(err_message)
             } else {
@@ -1,16 +1,34 @@
 use icu::locale::Locale;
+use rand::SeedableRng;
 use rand::prelude::SliceRandom;
-use rand::rng;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+pub mod audit;
 pub mod cards;
+pub mod compression;
+pub mod decoding;
 pub mod dice;
+pub mod diff;
 pub mod display_information;
 pub mod edit_distance;
+pub mod error;
 pub mod file_readers;
 pub mod file_writer;
+pub mod formats;
+pub mod generate;
+pub mod hunspell;
 pub mod list_manipulations;
+pub mod manifest;
+pub mod metadata;
 pub mod parsers;
+pub mod pipeline;
+pub mod recipe;
 pub mod schlinkert_pruning;
+use crate::audit::AuditRecord;
+use crate::error::TidyError;
 use crate::list_manipulations::*;
+use crate::metadata::*;
+use crate::pipeline::*;
 
 #[derive(Default, Debug, Clone)]
 pub struct TidyRequest {
@@ -21,6 +39,12 @@ pub struct TidyRequest {
     pub sort_by_length: bool,
     pub ignore_after_delimiter: Option<char>,
     pub ignore_before_delimiter: Option<char>,
+    pub tabular: Option<TabularOptions>,
+    pub score: Option<ScoreOptions>,
+    pub sort_by_score: bool,
+    /// What to do with the metadata of words that end up the same. If `None`, only
+    /// lines that are entirely the same (metadata included) are de-duplicated.
+    pub merge: Option<MergePolicy>,
     pub normalization_form: Option<String>,
     pub locale: String, // defaults to en-US
     pub to_lowercase: bool,
@@ -37,21 +61,34 @@ pub struct TidyRequest {
     pub should_delete_integers: bool,
     pub should_delete_after_first_delimiter: Option<char>,
     pub should_delete_before_first_delimiter: Option<char>,
-    pub reject_list: Option<Vec<String>>,
-    pub approved_list: Option<Vec<String>>,
-    pub homophones_list: Option<Vec<(String, String)>>,
+    pub reject_list: Option<MatchList>,
+    pub approved_list: Option<MatchList>,
+    pub homophones_list: Option<Vec<Vec<String>>>,
     pub minimum_length: Option<usize>,
     pub maximum_length: Option<usize>,
     pub maximum_shared_prefix_length: Option<usize>,
     pub minimum_edit_distance: Option<usize>,
     pub print_rand: Option<usize>,
     pub print_first: Option<usize>,
+    /// Names of stages to run in a particular order (see `pipeline::order_stages`)
+    pub stage_order: Option<Vec<String>>,
+    /// Seed for take_rand and print_rand, to make them reproducible (see `make_rng`)
+    pub seed: Option<u64>,
 }
 
-#[derive(PartialEq)]
-enum MetadataPosition {
-    Start,
-    End,
+/// Make the random number generator for Tidy's shuffles and samples. Given a seed,
+/// it makes the same choices every time (with the same version of Tidy). Without
+/// one, it's seeded by the operating system, so it makes different choices each run.
+/// ```
+/// use rand::Rng;
+/// use tidy::make_rng;
+/// assert_eq!(make_rng(Some(42)).random::<u64>(), make_rng(Some(42)).random::<u64>());
+/// ```
+pub fn make_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    }
 }
 
 /// Simple helper function that splits a `str` by a given substring `str`,
@@ -69,286 +106,312 @@ pub fn split_and_vectorize<'a>(string_to_split: &'a str, splitter: &str) -> Vec<
 /// a `TidyRequest` object -- which includes the word list --
 /// and performs whatever functions the user has requesteed to
 /// perform on the list.
-pub fn tidy_list(req: TidyRequest) -> Vec<String> {
-    // First, we need to do the two truncations
-    let mut list_to_tidy = req.list.clone();
-    list_to_tidy = match req.take_first {
-        Some(amount_to_take) => {
-            list_to_tidy.truncate(amount_to_take);
-            list_to_tidy
-        }
-        None => list_to_tidy,
+///
+/// Returns an error if the request's options can't be used (like an
+/// unknown locale or normalization form).
+pub fn tidy_list(req: TidyRequest) -> Result<Vec<String>, TidyError> {
+    Ok(tidy(req, false)?.0)
+}
+
+/// Like `tidy_list`, but also make an audit of the list: one record for each
+/// inputted line, saying whether its word was kept, and if not, which stage
+/// removed it and why.
+pub fn tidy_list_with_audit(
+    req: TidyRequest,
+) -> Result<(Vec<String>, Vec<AuditRecord>), TidyError> {
+    tidy(req, true)
+}
+
+/// Does the actual work of `tidy_list`. Only keeps track of which words were
+/// removed (and why) if making an audit, as that takes extra time.
+fn tidy(req: TidyRequest, audit: bool) -> Result<(Vec<String>, Vec<AuditRecord>), TidyError> {
+    // Check the options we'll need later up front, so we don't do a lot
+    // of work before finding a problem.
+    if let Some(ref nf) = req.normalization_form {
+        normalize_unicode("", nf)?;
+    }
+    if req.merge == Some(MergePolicy::KeepHighestScore) && req.score.is_none() {
+        return Err(TidyError::invalid_option(
+            "--merge",
+            &MergePolicy::KeepHighestScore.to_string(),
+            "need a --score-column to find which word has the highest score",
+        ));
+    }
+    // If we're going to sort, parse the given locale into a valid Locale
+    let loc: Option<Locale> = if req.sort_alphabetically || req.sort_by_length {
+        Some(req.locale.parse().map_err(|_| {
+            TidyError::invalid_option(
+                "--locale",
+                &req.locale,
+                "given locale is not parse-able. Try using form like 'en-US'; do not use underscores",
+            )
+        })?)
+    } else {
+        None
     };
-    list_to_tidy = match req.take_rand {
-        Some(amount_to_take) => {
-            let mut rng = rng();
-            list_to_tidy.shuffle(&mut rng);
-            list_to_tidy.truncate(amount_to_take);
-            list_to_tidy
+    // Keep track of where each line was in the inputted list, for the audit
+    let mut list_to_tidy: Vec<(usize, String)> = req.list.iter().cloned().enumerate().collect();
+    let mut removals: Vec<Removal> = vec![];
+    let mut record_removals = |lines: &[(usize, String)], stage: &str| {
+        if audit {
+            removals.extend(lines.iter().map(|(source, line)| Removal {
+                word: line.to_string(),
+                source: *source,
+                stage: stage.to_string(),
+                reason: None,
+            }));
         }
-        None => list_to_tidy,
     };
-    let mut tidied_list = vec![];
-    // Now we go word-by-word
-    for word in &list_to_tidy {
-        // METADATA-IGNORING WORD REMOVALS
+    // If the lines have scores, put the best words first, so that
+    // take_first (and whittling) take the best words.
+    if let Some(score) = req.score {
+        let lines_without_scores;
+        (list_to_tidy, lines_without_scores) = order_items_by_score(
+            list_to_tidy,
+            |(_source, line)| line,
+            score,
+            req.ignore_after_delimiter,
+            req.ignore_before_delimiter,
+            req.tabular,
+        );
+        if lines_without_scores > 0 {
+            eprintln!(
+                "WARNING: Found {} line(s) without a score in column {}. Putting them at the end of the list.",
+                lines_without_scores,
+                score.column + 1
+            );
+        }
+    }
+    // First, we need to do the two truncations
+    if let Some(amount_to_take) = req.take_first {
+        record_removals(
+            list_to_tidy.get(amount_to_take..).unwrap_or_default(),
+            "take-first",
+        );
+        list_to_tidy.truncate(amount_to_take);
+    }
+    // We use one generator for the whole run, so that a seed makes both take_rand
+    // and print_rand reproducible
+    let mut rng = make_rng(req.seed);
+    if let Some(amount_to_take) = req.take_rand {
+        list_to_tidy.shuffle(&mut rng);
+        record_removals(
+            list_to_tidy.get(amount_to_take..).unwrap_or_default(),
+            "take-rand",
+        );
+        list_to_tidy.truncate(amount_to_take);
+    }
+    let mut entries: Vec<Entry> = vec![];
+    for (source, word) in &list_to_tidy {
         // If user chose to ignore metadata, split the line into the word and the metadata
-        // based on given delimiter. Note that metadata may come before or after the word.
-        // We'll then do removals operations on the "word", ignoring metadata.
+        // based on given delimiter (or CSV/TSV column). Note that metadata may come before
+        // or after the word.
+        // We'll then do all the stages on the "word", ignoring metadata.
         // Later, we'll re-add the metadata to the word.
-
-        // We need delimiter to have a broad scope so that we can use it
-        // when we re-add the metadata at the end. Default to comma, but can be changed
-        // in match statement here.
-        let (mut new_word, delimiter, metadata, metadata_position) =
-            match (req.ignore_after_delimiter, req.ignore_before_delimiter) {
-                (Some(delimiter), None) => {
-                    // Parse delimiter. Currently this converts 's' to ' '
-                    // and 't' to '\t'.
-                    let delimiter = parse_delimiter(delimiter).unwrap();
-                    let split_vec = split_and_vectorize(word, &delimiter.to_string());
-                    if split_vec.len() == 1 {
-                        eprintln!("No metadata found for word: {:?}", word);
-                        (word.to_string(), Some(delimiter), None, None)
-                    } else {
-                        (
-                            split_vec[0].to_string(),
-                            Some(delimiter),
-                            Some(split_vec[1]),
-                            Some(MetadataPosition::End),
-                        )
-                    }
-                }
-                (None, Some(delimiter)) => {
-                    let delimiter = parse_delimiter(delimiter).unwrap();
-                    let split_vec = split_and_vectorize(word, &delimiter.to_string());
-                    if split_vec.len() == 1 {
-                        eprintln!("No metadata found for word: {:?}", word);
-                        (word.to_string(), Some(delimiter), None, None)
-                    } else {
-                        (
-                            split_vec[1].to_string(),
-                            Some(delimiter),
-                            Some(split_vec[0]),
-                            Some(MetadataPosition::Start),
-                        )
-                    }
-                }
-                (Some(ref _delimiter1), Some(ref _delimiter2)) => {
-                    // This situation should be caught and handled better
-                    // in src/main.rs, so this is really just in case.
-                    panic!("Can't ignore metadata on both sides currently")
-                }
-                (None, None) => (word.to_string(), None, None, None),
-            };
+        let (new_word, metadata) = split_off_metadata(
+            word,
+            req.ignore_after_delimiter,
+            req.ignore_before_delimiter,
+            req.tabular,
+        );
 
         // Trim new word, then normalize unicode if user gave an
         // nromalization form to use
-        new_word = match &req.normalization_form {
-            Some(nf) => match normalize_unicode(new_word.trim(), nf) {
-                Ok(word) => word,
-                Err(e) => panic!("{}", e),
-            },
-            None => {
-                // still need to trim
-                new_word.trim().to_string()
-            }
-        };
-
-        // WORD MODIFICATIONS
-        // For logic reasons, it's crucial that Tidy perform these word
-        // modifications BEFORE it runs word removals.
-        // If user has chosen to Ignore Metadata, we're guranteed
-        // that all of these will be None, so we don't have to worry
-        // about metadata loss due to de-duplication caused by word modification.
-        new_word = match req.should_delete_before_first_delimiter {
-            Some(delimiter) => {
-                delete_before_first_char(&new_word, parse_delimiter(delimiter).unwrap()).to_string()
-            }
-            None => new_word,
-        };
-        new_word = match req.should_delete_after_first_delimiter {
-            Some(delimiter) => {
-                delete_after_first_char(&new_word, parse_delimiter(delimiter).unwrap()).to_string()
-            }
-            None => new_word,
+        let new_word = match &req.normalization_form {
+            Some(nf) => normalize_unicode(new_word.trim(), nf)?,
+            None => new_word.trim().to_string(),
         };
-        if req.should_delete_integers && new_word.chars().any(|c| c.is_numeric()) {
-            new_word = delete_integers(new_word.to_string());
-        }
-        if req.should_delete_nonalphanumeric && new_word.chars().any(|c| c.is_alphanumeric()) {
-            new_word = delete_nonalphanumeric(new_word.to_string());
-        }
-        if req.to_lowercase {
-            new_word = new_word.to_ascii_lowercase();
-        }
-        if req.should_straighten_quotes {
-            new_word = straighten_quotes(&new_word).to_string();
+        if new_word.is_empty() {
+            record_removals(&[(*source, word.to_string())], "blank");
+        } else {
+            entries.push(Entry {
+                word: new_word,
+                metadata,
+                source: *source,
+            });
         }
+    }
 
-        new_word = new_word.trim().to_string();
-
-        // WORD REMOVALS
-        // Now that the words have been modified, we can move on to
-        // word removals.
-        // IF user has chosen to ignore any metadata, these should be the
-        // first edits that we do.
-        if req.should_remove_nonascii {
-            // https://doc.rust-lang.org/std/primitive.str.html#method.is_ascii
-            if !new_word.is_ascii() {
-                // If we're here, that means we already know that we
-                // do NOT want to add this word to our ouputted list.
-                // So we can just skip to the next word in our loop.
-                continue;
-            }
-        }
-        // For these other checks, we have to iterate through every individual
-        // character (char) of each word
-        if req.should_remove_nonalphanumeric && new_word.chars().any(|c| !c.is_alphanumeric()) {
-            continue;
-        }
-        if req.should_remove_nonalphabetic && new_word.chars().any(|c| !c.is_alphabetic()) {
-            continue;
-        }
-        if req.should_remove_non_latin_alphabetic
-            && new_word.chars().any(|chr| !is_latin_alphabetic(chr as u16))
-        {
-            continue;
-        }
-        if req.should_remove_integers && new_word.chars().any(|c| c.is_numeric()) {
+    // Now run the stages the user asked for. For logic reasons, by default
+    // Tidy performs word modifications BEFORE it runs word removals, but
+    // the user can choose a different order.
+    let mut stages = build_stages(&req);
+    if let Some(ref stage_order) = req.stage_order {
+        stages = order_stages(stages, stage_order)?;
+    }
+    let mut entries = if audit {
+        let (entries, stage_removals) = run_pipeline_with_removals(entries, &stages);
+        removals.extend(stage_removals);
+        entries
+    } else {
+        run_pipeline(entries, &stages)
+    };
+    // If a line appears more than once, only keep one of them, in the place of
+    // the first one. If the user gave a merge policy, lines with the same word
+    // count as duplicates too, and the policy decides which metadata to keep.
+    let mut kept_positions: HashMap<String, usize> = HashMap::new();
+    let mut unique_entries: Vec<Entry> = vec![];
+    for entry in entries {
+        let key = match req.merge {
+            Some(_) => entry.word.to_string(),
+            None => entry.line(),
+        };
+        let Some(&position) = kept_positions.get(&key) else {
+            kept_positions.insert(key, unique_entries.len());
+            unique_entries.push(entry);
             continue;
-        }
-        if let Some(ref reject_list) = req.reject_list {
-            if reject_list.contains(&new_word) {
-                continue;
-            }
-        }
-
-        if let Some(ref approved_list) = req.approved_list {
-            if !approved_list.contains(&new_word) {
-                continue;
-            }
         };
-
-        if let Some(minimum_length) = req.minimum_length {
-            if count_characters(&new_word) < minimum_length {
-                continue;
+        let kept = &mut unique_entries[position];
+        let duplicate_entry = match req.merge {
+            None | Some(MergePolicy::KeepFirst) => entry,
+            Some(MergePolicy::KeepHighestScore) => {
+                if has_higher_score(&entry, kept, &req) {
+                    std::mem::replace(kept, entry)
+                } else {
+                    entry
+                }
             }
-        };
-
-        if let Some(maximum_length) = req.maximum_length {
-            if count_characters(&new_word) > maximum_length {
-                continue;
+            Some(MergePolicy::Concatenate) => {
+                kept.metadata = match (kept.metadata.take(), entry.metadata.clone()) {
+                    (Some(metadata), Some(other_metadata)) => {
+                        Some(metadata.concatenate(other_metadata))
+                    }
+                    (metadata, other_metadata) => metadata.or(other_metadata),
+                };
+                entry
             }
         };
-
-        // trim whitespace
-        new_word = new_word.trim().to_string();
-
-        // If there was metadata, re-add it to the word now.
-        if !new_word.is_empty() {
-            if let Some(metadata) = metadata {
-                if metadata_position == Some(MetadataPosition::End) {
-                    new_word = new_word + &delimiter.unwrap().to_string() + metadata;
-                } else if metadata_position == Some(MetadataPosition::Start) {
-                    new_word = metadata.to_owned() + &delimiter.unwrap().to_string() + &new_word;
-                }
-            };
+        if audit {
+            removals.push(Removal::duplicate(
+                duplicate_entry,
+                &unique_entries[position],
+            ));
         }
+    }
+    entries = unique_entries;
+    let mut cut_entries: Vec<(Entry, &str)> = vec![];
 
-        // trim whitespace again
-        new_word = new_word.trim().to_string();
-        // The trim calls could have made new_word empty
-        // so need to check again
-        if !new_word.is_empty() {
-            tidied_list.push(new_word);
-        }
+    // User can choose to print a limited number of words from nearly finished (but still
+    // unsorted) list.
+    // Can do so from the beginning of the nearly finished list...
+    if let Some(amount_to_cut) = req.print_first {
+        let cut_from = amount_to_cut.min(entries.len());
+        cut_entries.extend(
+            entries
+                .drain(cut_from..)
+                .map(|entry| (entry, "print-first")),
+        );
     }
-    // Now truncate list, if requested
-    // Some operations are just a bit too complex for
-    // me to figure out how to do on a per-word basis
-    // at this time. Maybe something to revisit in the
-    // future
-    tidied_list = match req.homophones_list {
-        Some(homophones_list) => remove_homophones(tidied_list, homophones_list),
-        None => tidied_list,
-    };
-    // I think this is a good order for these next few operations,
-    // but I'm not super confident
-    tidied_list = match req.maximum_shared_prefix_length {
-        Some(maximum_shared_prefix_length) => {
-            guarantee_maximum_prefix_length(&tidied_list, maximum_shared_prefix_length)
-        }
-        None => tidied_list,
-    };
-    tidied_list = match req.minimum_edit_distance {
-        Some(minimum_edit_distance) => {
-            enfore_minimum_edit_distance(tidied_list, minimum_edit_distance)
-        }
-        None => tidied_list,
-    };
-    tidied_list = if req.should_remove_suffix_words {
-        remove_suffix_words(dedup_without_sorting(&mut tidied_list))
-    } else {
-        tidied_list
-    };
-    tidied_list = if req.should_remove_prefix_words {
-        remove_prefix_words(dedup_without_sorting(&mut tidied_list))
-    } else {
-        tidied_list
-    };
-    tidied_list = if req.should_schlinkert_prune {
-        schlinkert_prune(&dedup_without_sorting(&mut tidied_list))
+    // And/or can do so randomly
+    if let Some(amount_to_cut) = req.print_rand {
+        entries.shuffle(&mut rng);
+        let cut_from = amount_to_cut.min(entries.len());
+        cut_entries.extend(entries.drain(cut_from..).map(|entry| (entry, "print-rand")));
+    }
+    if audit {
+        removals.extend(cut_entries.into_iter().map(|(entry, stage)| Removal {
+            word: entry.word,
+            source: entry.source,
+            stage: stage.to_string(),
+            reason: None,
+        }));
+    }
+    let audit_records = if audit {
+        make_audit_records(&req.list, &entries, removals)
     } else {
-        tidied_list
+        vec![]
     };
 
+    // If there was metadata, re-add it to each word now.
+    let mut tidied_list: Vec<String> = entries.iter().map(Entry::line).collect();
+
     // Remove duplicate words
     tidied_list = dedup_without_sorting(&mut tidied_list);
 
-    // User can choose to print a limited number of words from nearly finished (but still
-    // unsorted) list.
-    // Can do so from the beginning of the nearly finished list...
-    tidied_list = match req.print_first {
-        Some(amount_to_cut) => {
-            tidied_list.truncate(amount_to_cut);
-            tidied_list
-        }
-        None => tidied_list,
-    };
-    // And/or can do so randomly
-    tidied_list = match req.print_rand {
-        Some(amount_to_cut) => {
-            let mut rng = rng();
-            tidied_list.shuffle(&mut rng);
-            tidied_list.truncate(amount_to_cut);
-            tidied_list
-        }
-        None => tidied_list,
-    };
     // Finally, sort list alphabetically, if the user didn't override this default behavior
-    if req.sort_alphabetically {
+    if req.sort_alphabetically
+        && let Some(ref loc) = loc
+    {
         // We used to just be content to run tidied_list.sort() here,
         // but that doesn't support non-English languages and
         // accented characters very well.
-
-        // First, parse the given locale into a valid Locale
-        let loc = req.locale.to_string();
-        let loc: Locale = loc.parse().expect("Error: Given locale is not parse-able. Trying using form like 'en-US'; do not use underscores.");
-
-        // Now use that Locale to sort the list more carefully
-        tidied_list = sort_carefully(tidied_list, loc);
+        // So we use the Locale we parsed above to sort the list more carefully
+        tidied_list = sort_carefully(tidied_list, loc.clone())?;
     }
-    if req.sort_by_length {
-        // First, parse the given locale into a valid Locale
-        let loc = req.locale.to_string();
-        let loc: Locale = loc.parse().expect("Error: given locale is not parse-able. Trying using form like 'en-US'; do not use underscores.");
-
-        tidied_list = sort_by_length(tidied_list, loc);
+    if req.sort_by_length
+        && let Some(loc) = loc
+    {
+        tidied_list = sort_by_length(tidied_list, loc)?;
+    }
+    if req.sort_by_score
+        && let Some(score) = req.score
+    {
+        tidied_list = order_by_score(
+            tidied_list,
+            score,
+            req.ignore_after_delimiter,
+            req.ignore_before_delimiter,
+            req.tabular,
+        )
+        .0;
     }
     // And remove duplicates one more time
     tidied_list = dedup_without_sorting(&mut tidied_list);
-    tidied_list
+    Ok((tidied_list, audit_records))
+}
+
+/// Whether an entry's line has a better score than another's. Lines without a
+/// score never have a better score.
+fn has_higher_score(entry: &Entry, other_entry: &Entry, req: &TidyRequest) -> bool {
+    let Some(score) = req.score else {
+        return false;
+    };
+    let score_of = |entry: &Entry| {
+        let line = match entry.metadata {
+            Some(ref metadata) => metadata.reattach(&entry.word),
+            None => entry.word.to_string(),
+        };
+        find_score(
+            &line,
+            score,
+            req.ignore_after_delimiter,
+            req.ignore_before_delimiter,
+            req.tabular,
+        )
+    };
+    match (score_of(entry), score_of(other_entry)) {
+        (Some(a), Some(b)) if score.lower_is_better => a < b,
+        (Some(a), Some(b)) => a > b,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Put together one audit record for each inputted line, in the order they were inputted
+fn make_audit_records(
+    inputted_lines: &[String],
+    kept_entries: &[Entry],
+    removals: Vec<Removal>,
+) -> Vec<AuditRecord> {
+    let mut records: Vec<AuditRecord> = inputted_lines
+        .iter()
+        .map(|line| AuditRecord {
+            line: line.to_string(),
+            word: String::new(),
+            removed_by: None,
+            reason: None,
+        })
+        .collect();
+    for entry in kept_entries {
+        records[entry.source].word = entry.word.to_string();
+    }
+    for removal in removals {
+        let record = &mut records[removal.source];
+        record.word = removal.word;
+        record.removed_by = Some(removal.stage);
+        record.reason = removal.reason;
+    }
+    records
 }
 
 use unicode_segmentation::UnicodeSegmentation;
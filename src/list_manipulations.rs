@@ -1,18 +1,126 @@
 use crate::count_characters;
 use crate::edit_distance::find_edit_distance;
+use crate::error::TidyError;
 use crate::schlinkert_pruning::get_sardinas_patterson_final_intersection;
 use memchr::memchr;
 use unicode_normalization::UnicodeNormalization;
 
 /// Normalize the Unicode of a string
 /// See <https://docs.rs/unicode-normalization/latest/unicode_normalization/trait.UnicodeNormalization.html#tymethod.nfc>
-pub fn normalize_unicode(word: &str, nf: &str) -> Result<String, String> {
+pub fn normalize_unicode(word: &str, nf: &str) -> Result<String, TidyError> {
     match nf.to_lowercase().as_str() {
         "nfc" => Ok(word.nfc().collect()),
         "nfd" => Ok(word.nfd().collect()),
         "nfkc" => Ok(word.nfkc().collect()),
         "nfkd" => Ok(word.nfkd().collect()),
-        _ => Err("Unknown Unicode Normalization Form received in arguments.\nPlease use one of the following normalization forms: nfc, nfd, nfkc, or nfkd.".to_string()),
+        _ => Err(TidyError::invalid_option(
+            "--normalization-form",
+            nf,
+            "please use one of the following normalization forms: nfc, nfd, nfkc, or nfkd",
+        )),
+    }
+}
+
+/// How loosely to match words against reject and approved lists. By default,
+/// words have to match exactly.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct MatchOptions {
+    /// "Café" matches "café" and "CAFÉ"
+    pub case_insensitive: bool,
+    /// "café" written with a combining accent matches "café" written with
+    /// a precomposed "é" (by comparing words in Normalization Form C)
+    pub normalized: bool,
+    /// "café" matches "cafe"
+    pub diacritic_insensitive: bool,
+}
+
+impl fmt::Display for MatchOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let modes = [
+            (self.case_insensitive, "case-insensitive"),
+            (self.normalized, "normalized"),
+            (self.diacritic_insensitive, "diacritic-insensitive"),
+        ];
+        let modes: Vec<&str> = modes
+            .iter()
+            .filter(|(enabled, _mode)| *enabled)
+            .map(|(_enabled, mode)| *mode)
+            .collect();
+        write!(f, "{}", modes.join(","))
+    }
+}
+
+// Serialized as it'd be typed on the command line, for recipes
+impl Serialize for MatchOptions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+use serde::{Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+use unicode_normalization::char::is_combining_mark;
+impl MatchOptions {
+    /// Produce the version of the word to use when matching. Two words
+    /// match if their keys are the same.
+    /// ```
+    /// use tidy::list_manipulations::MatchOptions;
+    /// let loose = MatchOptions { case_insensitive: true, normalized: true, diacritic_insensitive: true };
+    /// assert_eq!(loose.match_key("CAFÉ"), loose.match_key("cafe"));
+    /// assert_ne!(MatchOptions::default().match_key("CAFÉ"), MatchOptions::default().match_key("cafe"));
+    /// ```
+    pub fn match_key<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        let mut key = Cow::Borrowed(word);
+        if self.diacritic_insensitive {
+            // Decompose characters, so that accents become separate combining
+            // characters, then remove those
+            key = Cow::Owned(key.nfd().filter(|c| !is_combining_mark(*c)).nfc().collect());
+        } else if self.normalized {
+            key = Cow::Owned(key.nfc().collect());
+        }
+        if self.case_insensitive {
+            key = Cow::Owned(key.to_lowercase());
+        }
+        key
+    }
+}
+
+use std::collections::HashSet;
+use std::sync::Arc;
+/// A list of words to match other words against, like a reject or approved list.
+/// Its words are put into a HashSet once, when the list is made, so that checking
+/// each word against them is quick, even for long lists. Clones of the list share
+/// the same HashSet.
+/// ```
+/// use tidy::list_manipulations::{MatchList, MatchOptions};
+/// let options = MatchOptions { case_insensitive: true, ..Default::default() };
+/// let reject_list = MatchList::new(&["Café"], options);
+/// assert!(reject_list.contains("CAFÉ"));
+/// assert!(!reject_list.contains("cafe"));
+/// ```
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct MatchList {
+    keys: Arc<HashSet<String>>,
+    options: MatchOptions,
+}
+
+impl MatchList {
+    pub fn new<S: AsRef<str>>(words: &[S], options: MatchOptions) -> Self {
+        MatchList {
+            keys: Arc::new(
+                words
+                    .iter()
+                    .map(|word| options.match_key(word.as_ref()).to_string())
+                    .collect(),
+            ),
+            options,
+        }
+    }
+
+    /// Whether the given word matches any word on the list
+    pub fn contains(&self, word: &str) -> bool {
+        self.keys.contains(self.options.match_key(word).as_ref())
     }
 }
 
@@ -20,30 +128,39 @@ pub fn normalize_unicode(word: &str, nf: &str) -> Result<String, String> {
 use icu::collator::{options::*, *};
 // use icu::locale::locale;
 use icu::locale::Locale;
-/// Sort a Vector of words alphabetically, taking into account the locale of the words
-/// `.sorted()` words -> ["Zambia", "abbey", "eager", "enlever", "ezra", "zoo", "énigme"]
-/// sort_carefully words -> ["abbey", "eager", "énigme", "enlever", "ezra", "Zambia", "zoo"]
-pub fn sort_carefully(list: Vec<String>, loc: Locale) -> Vec<String> {
+/// Make a collator to compare words of the given locale with
+fn make_collator(loc: Locale) -> Result<CollatorBorrowed<'static>, TidyError> {
     // Examples:  https://github.com/unicode-org/icu4x/tree/main/components/collator#examples
     // Reference: https://docs.rs/icu/latest/icu/collator/index.html
     //            https://docs.rs/icu/latest/icu/locale/struct.Locale.html
     let mut options = CollatorOptions::default();
     options.strength = Some(Strength::Secondary); // Note this is not the locally defined passphrase Strength!
-    let collator = Collator::try_new(loc.into(), options).unwrap();
+    let locale_name = loc.to_string();
+    Collator::try_new(loc.into(), options).map_err(|e| {
+        TidyError::invalid_option(
+            "--locale",
+            &locale_name,
+            &format!("unable to sort words of this locale: {}", e),
+        )
+    })
+}
+
+/// Sort a Vector of words alphabetically, taking into account the locale of the words
+/// `.sorted()` words -> ["Zambia", "abbey", "eager", "enlever", "ezra", "zoo", "énigme"]
+/// sort_carefully words -> ["abbey", "eager", "énigme", "enlever", "ezra", "Zambia", "zoo"]
+pub fn sort_carefully(list: Vec<String>, loc: Locale) -> Result<Vec<String>, TidyError> {
+    let collator = make_collator(loc)?;
 
     let mut newly_sorted_list = list;
     newly_sorted_list.sort_by(|a, b| collator.compare(a, b));
-    newly_sorted_list
+    Ok(newly_sorted_list)
 }
 
 /// Sort by word length, with longest words first. For words of equal length, sorts
 /// word alphabetically, respecting inputted locale.
-pub fn sort_by_length(list: Vec<String>, loc: Locale) -> Vec<String> {
+pub fn sort_by_length(list: Vec<String>, loc: Locale) -> Result<Vec<String>, TidyError> {
     // Set up the collator again
-    let mut options = CollatorOptions::default();
-    options.strength = Some(Strength::Secondary);
-    // let collator = Collator::try_new(locale, options).unwrap();
-    let collator = Collator::try_new(loc.into(), options).unwrap();
+    let collator = make_collator(loc)?;
 
     let mut list = list;
     // Order by count_characters(w) descending, then within that,
@@ -53,7 +170,7 @@ pub fn sort_by_length(list: Vec<String>, loc: Locale) -> Vec<String> {
             .cmp(&count_characters(word1))
             .then_with(|| collator.compare(word1, word2))
     });
-    list
+    Ok(list)
 }
 
 /// Given a String (a word), delete all integers from the word.
@@ -154,6 +271,26 @@ pub fn guarantee_maximum_prefix_length(
     new_word_list
 }
 
+/// Like `guarantee_maximum_prefix_length`, but rather than preferring shorter
+/// words, prefer words that come earlier in the given list. Useful if the list is
+/// ordered by desirability (e.g. by word frequency). Keeps the order of the given list.
+pub fn guarantee_maximum_prefix_length_by_preference(
+    list: &[String],
+    maximum_shared_prefix_length: usize,
+) -> Vec<String> {
+    let mut prefixes_seen = HashSet::new();
+    let mut new_word_list = vec![];
+    for this_word in list {
+        // Words that are too short can't share a prefix of this length
+        if count_characters(this_word) < maximum_shared_prefix_length
+            || prefixes_seen.insert(get_prefix(this_word, maximum_shared_prefix_length))
+        {
+            new_word_list.push(this_word.to_string());
+        }
+    }
+    new_word_list
+}
+
 /// Executes Schlinkert prune. Attempts to make list uniquely decodable
 /// by removing the fewest number of code words possible. Adapted from
 /// Sardinas-Patterson algorithm.
@@ -221,7 +358,7 @@ pub fn get_prefix(word: &str, length: usize) -> String {
 /// assert_eq!(is_latin_alphabetic('ő' as u16), false);
 /// ```
 pub fn is_latin_alphabetic(chr: u16) -> bool {
-    (chr >= 65 && chr <= 90) || (chr >= 97 && chr <= 122)
+    (65..=90).contains(&chr) || (97..=122).contains(&chr)
 }
 
 /// Replaces curly or smart quotes with straight quotes.
@@ -273,6 +410,41 @@ pub fn remove_prefix_words(list: Vec<String>) -> Vec<String> {
     list_without_prefix_words
 }
 
+use std::collections::BTreeSet;
+/// Like `remove_prefix_words`, this makes the given list free of prefix words.
+/// But rather than always removing the prefix word ("news"), keep whichever word
+/// comes earlier in the given list, removing any later words that it is a prefix
+/// of, or that are a prefix of it. Useful if the list is ordered by desirability
+/// (e.g. by word frequency). Keeps the order of the given list.
+/// ```
+/// use tidy::list_manipulations::remove_prefix_words_by_preference;
+/// let list = vec!["news".to_string(), "newspaper".to_string(), "paper".to_string()];
+/// assert_eq!(remove_prefix_words_by_preference(list), vec!["news", "paper"]);
+/// ```
+pub fn remove_prefix_words_by_preference(list: Vec<String>) -> Vec<String> {
+    // Kept words, in alphabetical order, so that all words starting with a given
+    // word come right after it
+    let mut kept_words: BTreeSet<&str> = BTreeSet::new();
+    let mut words_to_keep = HashSet::new();
+    for word in &list {
+        let is_prefix_of_kept_word = kept_words
+            .range(word.as_str()..)
+            .next()
+            .is_some_and(|kept_word| kept_word.starts_with(word.as_str()));
+        let kept_word_is_prefix = word
+            .char_indices()
+            .skip(1)
+            .any(|(i, _)| kept_words.contains(&word[..i]));
+        if !is_prefix_of_kept_word && !kept_word_is_prefix {
+            kept_words.insert(word);
+            words_to_keep.insert(word.to_string());
+        }
+    }
+    list.into_iter()
+        .filter(|word| words_to_keep.contains(word))
+        .collect()
+}
+
 /// Remove suffix words from the given Vector of `String`s.
 ///
 /// A brief example: If both "news" and "newspaper" are on the inputted list
@@ -339,22 +511,68 @@ pub fn enfore_minimum_edit_distance(
     new_list
 }
 
-/// Takes the inputted word list and a Vector of tuples of Strings,
-/// each representing a pair of homophones, e.g. `("there", "their")`.
-/// The function outputs a new list in which, if both homophones
-/// are detected, the second homophone is removed.
-pub fn remove_homophones(list: Vec<String>, homophones: Vec<(String, String)>) -> Vec<String> {
-    let mut words_to_remove = vec![];
-    for pair_of_homophones in homophones {
-        if list.contains(&pair_of_homophones.0)
-            && list.contains(&pair_of_homophones.1)
-            && !(words_to_remove.contains(&pair_of_homophones.0)
-                || words_to_remove.contains(&pair_of_homophones.1))
-        {
-            words_to_remove.push(pair_of_homophones.1);
-        }
+/// Takes the inputted word list and a Vector of groups of homophones, e.g.
+/// `["to", "too", "two"]`. The function outputs a new list in which only
+/// one word of each group survives: the first one of the group found on the list.
+/// ```
+/// use tidy::list_manipulations::remove_homophones;
+/// let list = vec!["two".to_string(), "too".to_string(), "sun".to_string()];
+/// let homophones = vec![vec!["to".to_string(), "too".to_string(), "two".to_string()]];
+/// assert_eq!(remove_homophones(list, homophones), vec!["too", "sun"]);
+/// ```
+pub fn remove_homophones(list: Vec<String>, homophones: Vec<Vec<String>>) -> Vec<String> {
+    let words_on_list: HashSet<&str> = list.iter().map(|word| word.as_str()).collect();
+    let mut words_to_remove: HashSet<String> = HashSet::new();
+    for group_of_homophones in &homophones {
+        // If a word of this group was already removed because of another group,
+        // it can't be the one that survives.
+        let mut present_homophones = group_of_homophones.iter().filter(|word| {
+            words_on_list.contains(word.as_str()) && !words_to_remove.contains(*word)
+        });
+        // Keep the first one...
+        present_homophones.next();
+        // ...and remove the rest
+        let rest: Vec<String> = present_homophones.cloned().collect();
+        words_to_remove.extend(rest);
     }
-    let mut new_list = list.to_vec();
-    new_list.retain(|w| !words_to_remove.contains(w));
-    new_list
+    list.into_iter()
+        .filter(|word| !words_to_remove.contains(word))
+        .collect()
+}
+
+/// Like `remove_homophones`, but rather than keeping the homophone that comes first
+/// in its group, keep whichever homophone comes first in the given list. Useful if
+/// the list is ordered by desirability (e.g. by word frequency).
+pub fn remove_homophones_by_preference(
+    list: Vec<String>,
+    homophones: Vec<Vec<String>>,
+) -> Vec<String> {
+    let positions: HashMap<&str, usize> = list
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, word)| (word.as_str(), i))
+        .collect();
+    let mut words_to_remove: HashSet<String> = HashSet::new();
+    for group_of_homophones in &homophones {
+        let mut present_homophones: Vec<(usize, &String)> = group_of_homophones
+            .iter()
+            .filter(|word| !words_to_remove.contains(*word))
+            .filter_map(|word| {
+                positions
+                    .get(word.as_str())
+                    .map(|position| (*position, word))
+            })
+            .collect();
+        present_homophones.sort();
+        let rest: Vec<String> = present_homophones
+            .into_iter()
+            .skip(1)
+            .map(|(_position, word)| word.to_string())
+            .collect();
+        words_to_remove.extend(rest);
+    }
+    list.into_iter()
+        .filter(|word| !words_to_remove.contains(word))
+        .collect()
 }
@@ -1,21 +1,40 @@
-use clap::Parser;
+use clap::ArgGroup;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use serde::Serialize;
 use std::env;
 use std::path::Path;
 use std::path::PathBuf;
+use tidy::display_information::{make_attributes, make_list_free_of_metadata};
+use tidy::error::TidyError;
 use tidy::*;
 pub mod display_information;
 pub mod input_validations;
+use crate::audit::*;
+use crate::cards::{CardDeck, CardOptions};
+use crate::decoding::*;
+use crate::dice::count_dice_rolls;
+use crate::diff::*;
+use crate::display_information::uniquely_decodable::is_uniquely_decodable;
 use crate::file_readers::*;
 use crate::file_writer::*;
+use crate::formats::*;
+use crate::generate::*;
+use crate::hunspell::*;
 use crate::input_validations::*;
+use crate::list_manipulations::{MatchList, MatchOptions};
+use crate::manifest::*;
+use crate::metadata::*;
 use crate::parsers::*;
+use crate::recipe::*;
 
 /// Combine and clean word lists
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 #[clap(version, about, name = "tidy")]
+#[clap(group(ArgGroup::new("tabular").args(["csv", "tsv"])))]
+#[clap(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 struct Args {
     /// Path(s) for optional list of approved words. Can accept multiple
-    /// files.
+    /// files. Use '-' to read from stdin.
     #[clap(short = 'a', long = "approve")]
     approved_list: Option<Vec<PathBuf>>,
 
@@ -29,12 +48,43 @@ struct Args {
     #[clap(short = 'j', long = "json")]
     attributes_as_json: bool,
 
+    /// Write a CSV file explaining what happened to each inputted line: the word
+    /// that was kept or, if the word was removed, which operation removed it and
+    /// why (e.g. "prefix of newspaper"). Also prints how many words each operation
+    /// removed.
+    #[clap(long = "audit")]
+    audit: Option<PathBuf>,
+
+    /// Use all four suits of this many full decks of playing cards for --cards, rather
+    /// than just telling black cards from red ones. With one deck, strongly recommend
+    /// only using on lists with lengths that are powers of 52 (52, 52^2 = 2704, etc.).
+    /// With more decks, card codes start with the deck number.
+    #[clap(long = "card-decks", requires = "cards", value_parser = clap::value_parser!(u8).range(1..))]
+    card_decks: Option<u8>,
+
     /// Print playing card abbreviation next to each word.
     /// Strongly recommend only using on lists with lengths that are powers
     /// of 26 (26^1, 26^2, 26^3, etc.)
     #[clap(long = "cards")]
     cards: bool,
 
+    /// Column of inputted CSV or TSV files that contains the words. Can be a number
+    /// (starting at 1) or, if files have a header row, the name of the column. All
+    /// other columns are kept as metadata. Defaults to 1.
+    #[clap(long = "column", requires = "tabular", value_parser=parse_column_selector)]
+    column: Option<ColumnSelector>,
+
+    /// Ignore lines of inputted word lists, reject lists and approved lists that start
+    /// with this prefix (e.g. '#'), so that these lists can have comments in them.
+    #[clap(long = "comment-prefix")]
+    comment_prefix: Option<String>,
+
+    /// Treat inputted word lists as comma-separated values (CSV) files, which may
+    /// have quoted fields. Use --column to select the column that contains the words.
+    /// May not be used together with -g or -G options.
+    #[clap(long = "csv", conflicts_with_all = ["ignore_after_delimiter", "ignore_before_delimiter"])]
+    csv: bool,
+
     /// Debug mode
     #[clap(long = "debug")]
     debug: bool,
@@ -62,53 +112,129 @@ struct Args {
 
     /// Print dice roll before word in output. Set number of sides
     /// of dice. Must be between 2 and 36. Use 6 for normal dice.
-    #[clap(long = "dice")]
-    dice_sides: Option<u8>,
+    /// Can also be a set of different dice, separated by commas (like 20,12,10,8),
+    /// which are each rolled once. List length should then be the product of their
+    /// sides (which --print-rand and --whittle-to accept as 20*12*10*8).
+    #[clap(long = "dice", value_delimiter = ',')]
+    dice_sides: Option<Vec<u8>>,
 
     /// Dry run. Don't write new list to file or terminal.
     #[clap(long = "dry-run")]
     dry_run: bool,
 
-    /// Force overwrite of output file if it exists.
+    /// Text encoding of inputted files. Accepts utf-8, latin-1, windows-1252, utf-16le or
+    /// utf-16be. Defaults to utf-8. Files that start with a byte order mark (BOM) are
+    /// detected automatically.
+    #[clap(long = "encoding", value_parser=parse_encoding)]
+    encoding: Option<InputEncoding>,
+
+    /// Force overwrite of output file (or manifest, audit file or saved recipe) if it exists.
     #[clap(short = 'f', long = "force")]
     force_overwrite: bool,
 
-    /// Path(s) to file(s) containing homophone pairs. There must be one pair
-    /// of homophones per line, separated by a comma (sun,son). If BOTH words
-    /// are found on a list, the SECOND word is removed. File(s) can be a CSV
-    /// (with no column headers) or TXT file(s).
+    /// Format to print the new list in: text (the default), json, csv, markdown (a
+    /// table), rust (a constant), js (a module) or c-header. Formats other than text
+    /// put any dice rolls or cards, the word and any metadata in their own columns
+    /// (or fields), named by the header row of CSV or TSV files if there is one.
+    #[clap(long = "format", default_value = "text", value_parser=parse_output_format)]
+    format: OutputFormat,
+
+    /// First row of each inputted CSV or TSV file is a header row. Header rows are
+    /// not treated as words, and are printed back out at the top of the new list.
+    #[clap(long = "header", requires = "tabular")]
+    header: bool,
+
+    /// Path(s) to file(s) containing groups of homophones. There must be one group
+    /// of two or more homophones per line, separated by commas (sun,son or to,too,two).
+    /// Only the FIRST word of each group found on the list is kept. File(s) can be a CSV
+    /// (with no column headers) or TXT file(s). Use '-' to read from stdin.
     #[clap(long = "homophones")]
     homophones_list: Option<Vec<PathBuf>>,
 
+    /// Treat inputted word lists as Hunspell dictionaries (.dic files). Each needs an
+    /// affix file (.aff) with the same name in the same directory. By default, stems
+    /// are expanded into all their word forms using the affix rules.
+    #[clap(long = "hunspell", conflicts_with_all = ["tabular", "skip_rows_start", "skip_rows_end"])]
+    hunspell: bool,
+
+    /// Only apply the Hunspell affix rules with these flags, separated by commas
+    /// (e.g. S,D). Other affix rules are ignored.
+    #[clap(
+        long = "hunspell-flags",
+        requires = "hunspell",
+        conflicts_with = "hunspell_stems",
+        value_delimiter = ','
+    )]
+    hunspell_flags: Option<Vec<String>>,
+
+    /// Only take the stems from Hunspell dictionaries, without applying any affix rules
+    #[clap(long = "hunspell-stems", requires = "hunspell")]
+    hunspell_stems: bool,
+
     /// Ignore characters after the first instance of the specified delimiter until the end of line, treating
     /// anything before the delimiter as a word. Delimiter must be a single character (e.g., ','). Use 't'
     /// for tab and 's' for space. Helpful for ignoring metadata like word frequencies.
-    /// Works with attribute analysis and most word removal options, but not with word modifications
-    /// (like to lowercase). May not be used together with -d, -D or -G options.
+    /// Works with attribute analysis, word removals and word modifications (like to lowercase),
+    /// which only change the word (see --merge). Use with -G to ignore metadata on both sides of each word.
+    /// May not be used together with -d or -D options.
     #[clap(short = 'g', long = "ignore-after")]
     ignore_after_delimiter: Option<char>,
 
     /// Ignore characters before and including the first instance of the specified delimiter, treating
     /// anything after the delimiter as a word. Delimiter must be a single character (e.g., ','). Use 't'
     /// for tab and 's' for space. Helpful for ignoring metadata like word frequencies.
-    /// Works with attribute analysis and most word removal options, but not with word modifications
-    /// (like to lowercase). May not be used together with -d, -D or -g options.
+    /// Works with attribute analysis, word removals and word modifications (like to lowercase),
+    /// which only change the word (see --merge). Use with -g to ignore metadata on both sides of each word.
+    /// May not be used together with -d or -D options.
     #[clap(short = 'G', long = "ignore-before")]
     ignore_before_delimiter: Option<char>,
 
+    /// Line ending of the new list: lf (the default, as on Linux and macOS) or crlf
+    /// (as on Windows)
+    #[clap(long = "line-ending", default_value = "lf", value_parser=parse_line_ending)]
+    line_ending: LineEnding,
+
     /// Specify a locale for words on the list. Aids with sorting. Examples: en-US, es-ES. Defaults
     /// to system LANG. If LANG environmental variable is not set, uses en-US.
     #[clap(long = "locale")]
     locale: Option<String>,
 
+    /// Replace characters that are invalid in the given encoding with the Unicode
+    /// replacement character (�), rather than skipping lines with invalid characters.
+    /// Either way, Tidy will report the line numbers of these lines.
+    #[clap(long = "lossy")]
+    lossy: bool,
+
     /// Lowercase all words on new list
     #[clap(short = 'l', long = "lowercase")]
     to_lowercase: bool,
 
+    /// Also write a manifest of the new list, next to it with ".manifest.json" added to
+    /// its name. The manifest records the version of Tidy, all options used, the SHA-256
+    /// hashes of all inputted files and of the new list, and the new list's attributes.
+    #[clap(long = "manifest", requires = "output", conflicts_with = "dry_run")]
+    manifest: bool,
+
+    /// Loosen how words are matched against reject and approved lists. Accepts one or more
+    /// of case-insensitive, normalized (compares Unicode in NFC), and diacritic-insensitive,
+    /// separated by commas. For example, with --match case-insensitive,diacritic-insensitive
+    /// a reject list with "Café" on it will also reject "cafe" and "CAFÉ".
+    #[clap(long = "match", value_parser=parse_match_options)]
+    match_options: Option<MatchOptions>,
+
     /// Set maximum word length
     #[clap(short = 'M', long = "maximum-word-length")]
     maximum_length: Option<usize>,
 
+    /// Keep only one line of words with metadata (see -g, -G, --csv and --tsv) that end up
+    /// the same, like "Apple" and "apple" once lowercased. By default, only lines that are
+    /// entirely the same are removed. keep-first keeps the first word and its metadata,
+    /// keep-highest-score keeps the word with the best score (see --score-column), and
+    /// concatenate keeps the first word, with the metadata of all of the words joined by
+    /// semicolons.
+    #[clap(long = "merge", value_parser=parse_merge_policy)]
+    merge: Option<MergePolicy>,
+
     /// Set number of leading characters to get to a unique prefix,
     /// which can aid auto-complete functionality.
     /// Setting this value to say, 4, means that knowing the first
@@ -131,11 +257,22 @@ struct Args {
     #[clap(long = "sort-by-length", conflicts_with = "no_alpha_sort")]
     sort_by_length: bool,
 
+    /// Draw cards for --cards without replacement, like from a shuffled deck, so no
+    /// card appears twice in a word's card code. Drawing k cards from a deck of n
+    /// cards gives n!/(n-k)! card codes (e.g. 52 * 51 = 2652 codes for 2 cards).
+    #[clap(long = "no-replacement", requires = "cards")]
+    no_replacement: bool,
+
     /// Do NOT sort outputted list alphabetically. Preserves original list order.
     /// Note that duplicate lines and blank lines will still be removed.
     #[clap(short = 'O', long = "no-sort", conflicts_with = "sort_by_length")]
     no_alpha_sort: bool,
 
+    /// Sort outputted list by score (see --score-column), with the best scores first.
+    /// Words with the same score are sorted alphabetically.
+    #[clap(long = "sort-by-score", requires = "score_column", conflicts_with_all = ["no_alpha_sort", "sort_by_length"])]
+    sort_by_score: bool,
+
     /// If multiple word list files give, concatenate word lists in
     /// order given. Default behavior is to "blend" them, like dealing
     /// playing cards in reverse.
@@ -147,8 +284,21 @@ struct Args {
     #[clap(short = 'z', long = "normalization-form")]
     normalization_form: Option<String>,
 
+    /// Run the given operations in this order, separated by commas, e.g.
+    /// "schlinkert-prune,minimum-edit-distance". Named operations swap places with
+    /// each other; others run where they usually do. Operations are named after
+    /// their options: delete-before, delete-after, delete-integers,
+    /// delete-nonalphanumeric, lowercase, straighten, remove-nonascii,
+    /// remove-nonalphanumeric, remove-nonalphabetic, remove-non-latin-alphabetic,
+    /// remove-integers, reject, approve, minimum-word-length, maximum-word-length,
+    /// homophones, shared-prefix-length, minimum-edit-distance, remove-suffix,
+    /// remove-prefix, and schlinkert-prune (the default order).
+    #[clap(long = "order", value_delimiter = ',')]
+    order: Option<Vec<String>>,
+
     /// Path for outputted list file. If none given, generated word list
-    /// will be printed to terminal.
+    /// will be printed to terminal. If path ends in .gz, .xz or .zst, the
+    /// outputted list file will be compressed accordingly.
     #[clap(short = 'o', long = "output")]
     output: Option<PathBuf>,
 
@@ -179,6 +329,11 @@ struct Args {
     #[clap(long = "quiet")]
     quiet: bool,
 
+    /// Path to a recipe file (TOML, or JSON if it ends in .json) of options to use.
+    /// Options given on the command line override the recipe's. See --save-recipe.
+    #[clap(long = "recipe")]
+    recipe: Option<PathBuf>,
+
     /// Remove all words with integers in them from list
     #[clap(short = 'I', long = "remove-integers")]
     remove_integers: bool,
@@ -212,7 +367,7 @@ struct Args {
     remove_suffix_words: bool,
 
     /// Path(s) for optional list of words to reject. Can accept multiple
-    /// files.
+    /// files. Use '-' to read from stdin.
     #[clap(short = 'r', long = "reject")]
     reject_list: Option<Vec<PathBuf>>,
 
@@ -221,18 +376,43 @@ struct Args {
     #[clap(short = 's', long = "samples")]
     samples: bool,
 
+    /// Save all the options of this run, including any from a recipe, to a recipe file
+    /// (TOML, or JSON if it ends in .json), which can be used later with --recipe.
+    #[clap(long = "save-recipe")]
+    save_recipe: Option<PathBuf>,
+
+    /// Seed for the random number generator used by --take-rand, --print-rand and
+    /// --samples, so that running the same options again gives the same list. By
+    /// default, a new random seed is used each time.
+    #[clap(long = "seed")]
+    seed: Option<u64>,
+
     /// Use Sardinas-Patterson algorithm to remove words to make list
     /// uniquely decodable. Experimental!
     #[clap(short = 'K', long = "schlinkert-prune")]
     schlinkert_prune: bool,
 
-    /// Skip first number of lines from inputted files. Useful for dealing with headers like from
-    /// PGP signatures
+    /// Field of each line that holds a numeric score for the word, like a word frequency.
+    /// Fields are split by the -g and -G delimiters, or are the columns of CSV or TSV files
+    /// (which can also be given by name if files have a header row). Starts at 1. Words
+    /// with better scores are put first before taking words (--take-first, --whittle-to),
+    /// and are kept over other words when removing prefix words, enforcing a maximum
+    /// shared prefix length, or removing homophones.
+    #[clap(long = "score-column", value_parser=parse_column_selector)]
+    score_column: Option<ColumnSelector>,
+
+    /// Treat scores as ranks, where lower scores are better (1 is the best). By default,
+    /// higher scores are better.
+    #[clap(long = "score-is-rank", requires = "score_column")]
+    score_is_rank: bool,
+
+    /// Skip first number of lines from inputted files. Useful for dealing with headers. (PGP
+    /// signatures are removed automatically.)
     #[clap(long = "skip-rows-start")]
     skip_rows_start: Option<usize>,
 
-    /// Skip last number of lines from inputted files. Useful for dealing with footers like from
-    /// PGP signatures.
+    /// Skip last number of lines from inputted files. Useful for dealing with footers. (PGP
+    /// signatures are removed automatically.)
     #[clap(long = "skip-rows-end")]
     skip_rows_end: Option<usize>,
 
@@ -254,6 +434,29 @@ struct Args {
     #[clap(long = "take-rand", value_parser=eval_list_length)]
     take_rand: Option<usize>,
 
+    /// Template for each line of the new list, like "{index}:{dice} {word}".
+    /// Placeholders are {index} (starting at 1), {dice} or {cards}, {word}, {metadata}
+    /// (see -g and -G) and {line} (word and metadata). Use {{ and }} for literal braces.
+    /// Can't be used with --format.
+    #[clap(long = "template", conflicts_with = "format", value_parser=parse_line_template)]
+    template: Option<LineTemplate>,
+
+    /// Treat inputted word lists as tab-separated values (TSV) files. Use --column to
+    /// select the column that contains the words. May not be used together with -g or
+    /// -G options.
+    #[clap(long = "tsv", conflicts_with_all = ["ignore_after_delimiter", "ignore_before_delimiter"])]
+    tsv: bool,
+
+    /// When blending multiple inputted word lists, take this many words from each list in
+    /// turn, separated by commas. For example, 3,1 takes three words from the first list
+    /// for every one word from the second. Must give one weight per inputted list.
+    #[clap(
+        long = "weights",
+        value_delimiter = ',',
+        conflicts_with = "concat_lists"
+    )]
+    weights: Option<Vec<usize>>,
+
     /// Whittle list exactly to a specified length, only taking minimum number of words
     /// from the beginning of inputted list(s).
     /// If the outputted list is not exactly the specified length, it will try again by taking a
@@ -275,20 +478,216 @@ struct Args {
 
     /// Word list input files. Can be more than one, in which case
     /// they'll be combined and de-duplicated. Requires at least
-    /// one file.
+    /// one file. Use '-' to read a word list from stdin.
     #[clap(name = "Inputted Word Lists", required = true)]
+    // Recipes find each option's value by its clap ID, which is this name
+    #[serde(rename = "Inputted Word Lists")]
     inputted_word_lists: Vec<PathBuf>,
+
+    #[clap(subcommand)]
+    #[serde(skip)]
+    command: Option<TidyCommand>,
 }
 
-fn main() -> Result<(), String> {
-    let opt = Args::parse();
+/// Things Tidy can do other than making a new list
+#[derive(Subcommand, Debug)]
+enum TidyCommand {
+    /// Compare two versions of a word list
+    ///
+    /// Shows added, removed and kept words, and how the lists' attributes changed.
+    /// If both lists have dice rolls (or other codes) before their words, also shows
+    /// which codes now mean a different word.
+    Diff(DiffArgs),
+    /// Generate secure passphrases from a word list
+    ///
+    /// Words are chosen with a cryptographically secure random number generator, and
+    /// the exact entropy of each passphrase is printed. Lists with dice rolls (or other
+    /// codes) before their words can be used as they are.
+    Generate(GenerateArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// Compare more attributes of the lists. Can be used more than once, like -A
+    #[clap(short = 'A', long = "attributes", action = clap::ArgAction::Count)]
+    attributes: u8,
+
+    /// Print the differences in JSON format
+    #[clap(short = 'j', long = "json")]
+    json: bool,
+
+    /// The old version of the word list. Use '-' to read from stdin.
+    old_list: PathBuf,
+
+    /// The new version of the word list. Use '-' to read from stdin.
+    new_list: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct GenerateArgs {
+    /// How to capitalize words: as-is, lower, upper or first (capitalize the first
+    /// letter of each word). Words that become the same when capitalized are only
+    /// counted once.
+    #[clap(short = 'C', long = "capitalize", default_value = "as-is", value_parser=parse_capitalization)]
+    capitalization: Capitalization,
+
+    /// Number of passphrases to generate
+    #[clap(short = 'c', long = "count", default_value_t = 1)]
+    count: usize,
+
+    /// Number of random digits to add to the end of each passphrase
+    #[clap(short = 'd', long = "digits", default_value_t = 0)]
+    digits: usize,
+
+    /// Number of words in each passphrase
+    #[clap(short = 'n', long = "words", default_value_t = 6)]
+    number_of_words: usize,
+
+    /// Don't print the entropy of the passphrases
+    #[clap(short = 'q', long = "quiet")]
+    quiet: bool,
+
+    /// Put this between words
+    #[clap(short = 's', long = "separator", default_value = " ")]
+    separator: String,
+
+    /// Word list to choose words from. Use '-' to read from stdin.
+    list: PathBuf,
+}
+
+/// Parse the command-line arguments. If the user gave a recipe, add its options to the
+/// command line, unless the user already gave the same option on the command line.
+/// Returns the parsed arguments and the recipe of all options used.
+fn parse_args_with_recipe() -> Result<(Args, Recipe), String> {
+    let command_line: Vec<String> = env::args().collect();
+    // First, just find the recipe, ignoring any other errors (like missing inputted
+    // word lists, which may be in the recipe). Help and version still print and exit.
+    let command_line_matches = Args::command()
+        .ignore_errors(true)
+        .try_get_matches_from(&command_line)
+        .unwrap_or_else(|e| e.exit());
+    let arguments = match command_line_matches.get_one::<PathBuf>("recipe") {
+        Some(recipe_path) => {
+            let recipe = read_recipe(recipe_path)?;
+            let (recipe_options, recipe_inputs) = recipe_to_arguments(
+                &recipe,
+                recipe_path,
+                &Args::command(),
+                &command_line_matches,
+            )?;
+            let mut arguments = vec![command_line[0].clone()];
+            arguments.extend(recipe_options);
+            arguments.extend(command_line[1..].iter().cloned());
+            arguments.extend(recipe_inputs);
+            arguments
+        }
+        None => command_line,
+    };
+    let matches = Args::command().get_matches_from(arguments);
+    let opt = Args::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+    let recipe = effective_recipe(&opt, &Args::command(), &matches)?;
+    Ok((opt, recipe))
+}
+
+fn main() {
+    // Print any error as a diagnostic for the user, rather than its Debug form
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Compare two word lists (the `tidy diff` subcommand)
+fn run_diff(diff_args: DiffArgs) -> Result<(), String> {
+    if is_stdin(&diff_args.old_list) && is_stdin(&diff_args.new_list) {
+        return Err("Error: Can only read one of the lists from stdin.".to_string());
+    }
+    let read_list = |path: &PathBuf| {
+        make_vec_from_filenames(
+            std::slice::from_ref(path),
+            false,
+            None,
+            None,
+            &ReadOptions::default(),
+        )
+    };
+    let diff = diff_lists(
+        &read_list(&diff_args.old_list)?,
+        &read_list(&diff_args.new_list)?,
+        diff_args.attributes,
+    )?;
+    if diff_args.json {
+        print_diff_as_json(&diff);
+    } else {
+        print_diff(&diff);
+    }
+    Ok(())
+}
+
+fn run_generate(generate_args: GenerateArgs) -> Result<(), String> {
+    let lines = make_vec_from_filenames(
+        std::slice::from_ref(&generate_args.list),
+        false,
+        None,
+        None,
+        &ReadOptions::default(),
+    )?;
+    let list = make_generator_list(&lines, generate_args.capitalization)?;
+    let options = PassphraseOptions {
+        number_of_words: generate_args.number_of_words,
+        separator: generate_args.separator,
+        capitalization: generate_args.capitalization,
+        digits: generate_args.digits,
+    };
+    // ThreadRng is a cryptographically secure generator, seeded by the operating system
+    let mut rng = rand::rng();
+    for _n in 0..generate_args.count {
+        println!("{}", generate_passphrase(&list, &options, &mut rng));
+    }
+    if !generate_args.quiet {
+        eprintln!(
+            "\nEach passphrase has {:.3} bits of entropy ({} words from a list of {} words{})",
+            calc_passphrase_entropy(list.len(), &options),
+            options.number_of_words,
+            list.len(),
+            match options.digits {
+                0 => String::new(),
+                1 => ", plus 1 digit".to_string(),
+                digits => format!(", plus {} digits", digits),
+            }
+        );
+        if options.separator.is_empty() && !is_uniquely_decodable(&list) {
+            eprintln!(
+                "WARNING: This list isn't uniquely decodable, so without a separator, some passphrases can be made in more than one way and the actual entropy may be lower. Consider using a separator."
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let (opt, recipe) = parse_args_with_recipe()?;
+    if let Some(ref recipe_path) = opt.save_recipe {
+        if !opt.force_overwrite && recipe_path.exists() {
+            return Err(
+                "Specified recipe file already exists. Use --force flag to force an overwrite."
+                    .to_string(),
+            );
+        }
+        write_recipe(&recipe, &Args::command(), recipe_path)?;
+    }
     if opt.debug {
         eprintln!("Received args: {:?}", opt);
     }
+    match opt.command {
+        Some(TidyCommand::Diff(diff_args)) => return run_diff(diff_args),
+        Some(TidyCommand::Generate(generate_args)) => return run_generate(generate_args),
+        None => {}
+    }
 
     // Some initial validations
     // Check given number of dice sides
-    match validate_dice_sides(opt.dice_sides) {
+    match validate_dice_sides(opt.dice_sides.as_deref()) {
         Ok(()) => (),
         Err(e) => {
             return Err(e.to_string());
@@ -302,6 +701,91 @@ fn main() -> Result<(), String> {
         }
     }
 
+    // Make sure user only asked us to read from stdin once
+    let all_inputted_files: Vec<&PathBuf> = opt
+        .inputted_word_lists
+        .iter()
+        .chain(opt.reject_list.iter().flatten())
+        .chain(opt.approved_list.iter().flatten())
+        .chain(opt.homophones_list.iter().flatten())
+        .collect();
+    match validate_stdin_usage(&all_inputted_files) {
+        Ok(()) => (),
+        Err(e) => {
+            return Err(e.to_string());
+        }
+    }
+    if let Some(ref weights) = opt.weights {
+        validate_weights(weights, opt.inputted_word_lists.len())?;
+    }
+    if opt.hunspell && opt.inputted_word_lists.iter().any(|file| is_stdin(file)) {
+        return Err(
+            "Error: Can't read Hunspell dictionaries from stdin, since they need an affix file next to them.".to_string(),
+        );
+    }
+    // Check if output file (or any other file we'd write) exists, before doing any work
+    if let Some(ref output_file_name) = opt.output
+        && !opt.force_overwrite
+        && Path::new(output_file_name).exists()
+    {
+        return Err(
+            "Specified output file already exists. Use --force flag to force an overwrite."
+                .to_string(),
+        );
+    }
+    if opt.manifest
+        && let Some(ref output) = opt.output
+        && !opt.force_overwrite
+        && manifest_path(output).exists()
+    {
+        return Err(
+            "Manifest file for the output file already exists. Use --force flag to force an overwrite."
+                .to_string(),
+        );
+    }
+    if let Some(ref audit_path) = opt.audit
+        && !opt.force_overwrite
+        && audit_path.exists()
+    {
+        return Err(
+            "Specified audit file already exists. Use --force flag to force an overwrite."
+                .to_string(),
+        );
+    }
+
+    // Hash the inputted files for the manifest before reading them
+    let manifest_inputs = if opt.manifest {
+        let mut inputs: Vec<PathBuf> = all_inputted_files
+            .iter()
+            .map(|path| path.to_path_buf())
+            .collect();
+        if opt.hunspell {
+            inputs.extend(
+                opt.inputted_word_lists
+                    .iter()
+                    .map(|dictionary| dictionary.with_extension("aff")),
+            );
+        }
+        inputs.extend(opt.recipe.iter().cloned());
+        inputs
+            .iter()
+            .map(|path| hash_file(path))
+            .collect::<Result<Vec<HashedFile>, TidyError>>()?
+    } else {
+        vec![]
+    };
+
+    if let Some(ref template) = opt.template
+        && template.uses_codes()
+        && !opt.cards
+        && opt.dice_sides.is_none()
+    {
+        return Err(
+            "Error: Template has a {dice} or {cards} placeholder, but neither --dice nor --cards was given."
+                .to_string(),
+        );
+    }
+
     if opt.cards && opt.dice_sides.is_some() {
         return Err(
             "Error: Cannot use dice and cards. Must be either cards or dice or neither."
@@ -321,16 +805,6 @@ fn main() -> Result<(), String> {
         }
     }
 
-    // Check if output file exists
-    if let Some(ref output_file_name) = opt.output {
-        if !opt.force_overwrite && Path::new(output_file_name).exists() {
-            return Err(
-                "Specified output file already exists. Use --force flag to force an overwrite."
-                    .to_string(),
-            );
-        }
-    }
-
     // Determine if this is a niche case in which whittle_to would be a smarter choice
     // than (either) print_first or print_rand.
     if (opt.print_first.is_some() || opt.print_rand.is_some())
@@ -350,22 +824,116 @@ fn main() -> Result<(), String> {
         }
     }
 
+    let read_options = ReadOptions {
+        encoding: opt.encoding.unwrap_or_default(),
+        lossy: opt.lossy,
+        comment_prefix: opt.comment_prefix.clone(),
+    };
+
+    // Read in the inputted word lists. If they're CSV or TSV files, we may
+    // need to remove header rows and figure out which column holds the words.
+    let mut word_lists_by_file = if opt.hunspell {
+        let hunspell_options = HunspellOptions {
+            stems_only: opt.hunspell_stems,
+            flags: opt.hunspell_flags.clone(),
+        };
+        read_hunspell_dictionaries_by_file(
+            &opt.inputted_word_lists,
+            &hunspell_options,
+            &read_options,
+        )?
+    } else {
+        read_word_lists_by_file(
+            &opt.inputted_word_lists,
+            opt.skip_rows_start,
+            opt.skip_rows_end,
+            &read_options,
+        )?
+    };
+    let tabular_format = if opt.csv {
+        Some(TabularFormat::Csv)
+    } else if opt.tsv {
+        Some(TabularFormat::Tsv)
+    } else {
+        None
+    };
+    // Quoted fields of CSV files may have line breaks in them, so read each
+    // file as records, rather than lines
+    let mut header_row = None;
+    if let Some(format) = tabular_format {
+        for (word_list, filename) in word_lists_by_file.iter_mut().zip(&opt.inputted_word_lists) {
+            *word_list =
+                group_into_records(std::mem::take(word_list), format).map_err(|reason| {
+                    TidyError::MalformedFile {
+                        path: filename.to_path_buf(),
+                        reason,
+                    }
+                })?;
+        }
+        if opt.header {
+            header_row =
+                remove_header_rows(&mut word_lists_by_file, &opt.inputted_word_lists, format)?;
+        }
+    }
+    let header_rows = header_row.as_slice();
+    let tabular = match tabular_format {
+        Some(format) => {
+            let column = opt.column.unwrap_or(ColumnSelector::Number(1));
+            Some(TabularOptions {
+                format,
+                word_column: resolve_column("--column", &column, header_rows, format)?,
+            })
+        }
+        None => None,
+    };
+    let score = match opt.score_column {
+        Some(column) => {
+            let column = match (tabular, column) {
+                (Some(tabular), column) => {
+                    resolve_column("--score-column", &column, header_rows, tabular.format)?
+                }
+                (None, ColumnSelector::Number(number))
+                    if opt.ignore_after_delimiter.is_some()
+                        || opt.ignore_before_delimiter.is_some() =>
+                {
+                    number - 1
+                }
+                (None, ColumnSelector::Name(_)) => {
+                    return Err(
+                        "Error: Can only find a score column by name in CSV or TSV files with a header row.".to_string(),
+                    );
+                }
+                (None, ColumnSelector::Number(_)) => {
+                    return Err(
+                        "Error: --score-column needs to know how to split lines into fields. Please also use -g, -G, --csv or --tsv.".to_string(),
+                    );
+                }
+            };
+            Some(ScoreOptions {
+                column,
+                lower_is_better: opt.score_is_rank,
+            })
+        }
+        None => None,
+    };
+
+    let match_options = opt.match_options.unwrap_or_default();
+
     // OK let's do this. Make a Tidy request.
     // While it's not declared as mutable here, we will reassign it
     // it later, unfortunately.
     let this_tidy_request = TidyRequest {
-        list: make_vec_from_filenames(
-            &opt.inputted_word_lists,
-            opt.concat_lists,
-            opt.skip_rows_start,
-            opt.skip_rows_end,
-        ),
+        list: combine_lists(word_lists_by_file, opt.concat_lists, opt.weights.as_deref()),
         take_first: opt.take_first,
         take_rand: opt.take_rand,
         sort_alphabetically: !opt.no_alpha_sort,
         sort_by_length: opt.sort_by_length,
         ignore_after_delimiter: opt.ignore_after_delimiter,
         ignore_before_delimiter: opt.ignore_before_delimiter,
+        tabular,
+        score,
+        sort_by_score: opt.sort_by_score,
+        merge: opt.merge,
         to_lowercase: opt.to_lowercase,
         normalization_form: opt.normalization_form,
         locale: match opt.locale {
@@ -391,37 +959,48 @@ fn main() -> Result<(), String> {
 
         // If given more than one file of reject words, combine them
         // right here.
-        reject_list: opt
-            .reject_list
-            .map(|list_of_files| make_vec_from_filenames(&list_of_files, true, None, None)),
+        reject_list: match opt.reject_list {
+            Some(list_of_files) => Some(MatchList::new(
+                &make_vec_from_filenames(&list_of_files, true, None, None, &read_options)?,
+                match_options,
+            )),
+            None => None,
+        },
         // Likewise with approved word lists
-        approved_list: opt
-            .approved_list
-            .map(|list_of_files| make_vec_from_filenames(&list_of_files, true, None, None)),
+        approved_list: match opt.approved_list {
+            Some(list_of_files) => Some(MatchList::new(
+                &make_vec_from_filenames(&list_of_files, true, None, None, &read_options)?,
+                match_options,
+            )),
+            None => None,
+        },
         // And homophones
-        homophones_list: opt
-            .homophones_list
-            .map(|list_of_files| read_homophones_list_from_filenames(&list_of_files)),
+        homophones_list: match opt.homophones_list {
+            Some(list_of_files) => Some(read_homophones_list_from_filenames(
+                &list_of_files,
+                &read_options,
+            )?),
+            None => None,
+        },
         minimum_length: opt.minimum_length,
         maximum_length: opt.maximum_length,
         maximum_shared_prefix_length: opt.maximum_shared_prefix_length,
         minimum_edit_distance: opt.minimum_edit_distance,
         print_rand: opt.print_rand,
         print_first: opt.print_first,
+        stage_order: opt.order,
+        seed: opt.seed,
     };
 
-    let (ignore_before_delimiter, ignore_after_delimiter) = match validate_and_parse_ignore_options(
-        &this_tidy_request,
-        opt.dice_sides,
-        opt.print_dice_sides_as_their_base,
-    ) {
-        Ok((ignore_before_delimiter, ignore_after_delimiter)) => {
-            (ignore_before_delimiter, ignore_after_delimiter)
-        }
-        Err(e) => {
-            return Err(e.to_string());
-        }
-    };
+    let (ignore_after_delimiter, ignore_before_delimiter) =
+        match validate_and_parse_ignore_options(&this_tidy_request) {
+            Ok((ignore_after_delimiter, ignore_before_delimiter)) => {
+                (ignore_after_delimiter, ignore_before_delimiter)
+            }
+            Err(e) => {
+                return Err(e.to_string());
+            }
+        };
 
     // Parse provided "whittle string" for a length_to_whittle_to and an
     // optional starting point.
@@ -431,7 +1010,7 @@ fn main() -> Result<(), String> {
                 (this_tidy_request, length_to_whittle_to, starting_point)
             }
             Err(e) => {
-                return Err(e);
+                return Err(e.into());
             }
         };
 
@@ -439,19 +1018,27 @@ fn main() -> Result<(), String> {
     // If we have a length_to_whittle_to and a starting_point, we know we're
     // whittling, which is (still) a bit too complicated for my tastes. But we
     // need a while loop here.
+    // Only make an audit if the user asked for one, as it takes extra time
+    let run_tidy = |req: TidyRequest| -> Result<(Vec<String>, Vec<AuditRecord>), String> {
+        if opt.audit.is_some() {
+            Ok(tidy_list_with_audit(req)?)
+        } else {
+            Ok((tidy_list(req)?, vec![]))
+        }
+    };
     let mut this_list_length = 0;
-    let tidied_list = match (length_to_whittle_to, starting_point) {
+    let (tidied_list, audit_records) = match (length_to_whittle_to, starting_point) {
         (Some(our_length_to_whittle_to), Some(mut our_starting_point)) => {
-            let mut this_tidied_list = vec![];
+            let mut this_tidied_list = (vec![], vec![]);
             while this_list_length != our_length_to_whittle_to {
                 // Edit this_tidy_request to have our new starting point
                 this_tidy_request.take_first = Some(our_starting_point);
 
                 // This clone might be too expensice. maybe tidy_list can take a
                 // reference?
-                this_tidied_list = tidy_list(this_tidy_request.clone());
+                this_tidied_list = run_tidy(this_tidy_request.clone())?;
 
-                this_list_length = this_tidied_list.len();
+                this_list_length = this_tidied_list.0.len();
                 our_starting_point = get_new_starting_point_guess(
                     our_starting_point,
                     this_list_length,
@@ -472,26 +1059,106 @@ fn main() -> Result<(), String> {
             // In all other cases, `whittle_to` option not specified, so
             // proceed as normal, sending all parameters in this_tidied_list
             // as they are just once.
-            tidy_list(this_tidy_request)
+            run_tidy(this_tidy_request)?
         }
     };
 
+    if let Some(ref audit_path) = opt.audit {
+        write_audit(&audit_records, audit_path)?;
+        if !opt.quiet {
+            display_audit_summary(&audit_records);
+        }
+    }
+
+    // A set of different dice is only rolled once, so it can't number a longer list
+    if let Some(ref dice_sides) = opt.dice_sides
+        && dice_sides.len() > 1
+    {
+        let dice_names = dice_sides
+            .iter()
+            .map(|sides| sides.to_string())
+            .collect::<Vec<String>>();
+        let rolls = count_dice_rolls(dice_sides).unwrap_or(usize::MAX);
+        if tidied_list.len() > rolls {
+            return Err(format!(
+                "Error: New list has {} words, but dice with {} sides only have {} different rolls. Use --print-rand {} or --whittle-to {} to cut the list to size.",
+                tidied_list.len(),
+                dice_names.join(","),
+                rolls,
+                dice_names.join("*"),
+                dice_names.join("*"),
+            ));
+        } else if tidied_list.len() < rolls && !opt.quiet {
+            eprintln!(
+                "WARNING: New list has {} words, but dice with {} sides have {} different rolls, so some rolls won't match a word.",
+                tidied_list.len(),
+                dice_names.join(","),
+                rolls
+            );
+        }
+    }
+
+    // Attributes for the manifest need to be found before the list is printed
+    let manifest_attributes = if opt.manifest && !tidied_list.is_empty() {
+        let words = make_list_free_of_metadata(
+            &tidied_list,
+            ignore_after_delimiter,
+            ignore_before_delimiter,
+            tabular,
+        );
+        Some(make_attributes(
+            &words,
+            opt.attributes.max(1),
+            false,
+            opt.seed,
+        )?)
+    } else {
+        None
+    };
     // Next, we figure out what to print where
     let this_print_request = PrintRequest {
         tidied_list,
         dry_run: opt.dry_run,
         quiet: opt.quiet,
-        output: opt.output,
-        cards: opt.cards,
+        output: opt.output.clone(),
+        cards: if opt.cards {
+            Some(CardOptions {
+                deck: match opt.card_decks {
+                    Some(decks) => CardDeck::FullDecks(decks),
+                    None => CardDeck::BlackAndRed,
+                },
+                without_replacement: opt.no_replacement,
+            })
+        } else {
+            None
+        },
         dice_sides: opt.dice_sides,
         print_dice_sides_as_their_base: opt.print_dice_sides_as_their_base,
         attributes: opt.attributes,
         attributes_as_json: opt.attributes_as_json,
         samples: opt.samples,
+        seed: opt.seed,
         ignore_before_delimiter,
         ignore_after_delimiter,
+        tabular,
+        header_row: header_row.clone(),
+        format: opt.format,
+        template: opt.template,
+        line_ending: opt.line_ending,
     };
-    print_list(this_print_request);
+    print_list(this_print_request)?;
+
+    if opt.manifest
+        && let Some(ref output) = opt.output
+    {
+        let manifest = Manifest::new(
+            recipe,
+            manifest_inputs,
+            hash_file(output)?,
+            manifest_attributes,
+        );
+        write_manifest(&manifest, &manifest_path(output))?;
+    }
 
     Ok(())
 }
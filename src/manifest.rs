@@ -0,0 +1,103 @@
+//! A manifest is a JSON file written next to an outputted list that records how
+//! the list was made: the version of Tidy, the options used, SHA-256 hashes of
+//! every inputted file and of the list itself, and the list's attributes. That
+//! way, anyone reviewing a published list can check that it is what it says it is.
+
+use crate::display_information::ListAttributes;
+use crate::error::TidyError;
+use crate::file_readers::is_stdin;
+use crate::file_writer::write_atomically;
+use crate::recipe::Recipe;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize)]
+pub struct Manifest {
+    pub tidy_version: String,
+    /// All the options used, in the same form as a recipe
+    pub options: Recipe,
+    pub inputs: Vec<HashedFile>,
+    pub output: HashedFile,
+    /// Attributes of the outputted list, if it has any words
+    pub attributes: Option<ListAttributes>,
+}
+
+impl Manifest {
+    pub fn new(
+        options: Recipe,
+        inputs: Vec<HashedFile>,
+        output: HashedFile,
+        attributes: Option<ListAttributes>,
+    ) -> Self {
+        Manifest {
+            tidy_version: env!("CARGO_PKG_VERSION").to_string(),
+            options,
+            inputs,
+            output,
+            attributes,
+        }
+    }
+}
+
+/// A file and the SHA-256 hash of its contents
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HashedFile {
+    pub path: String,
+    /// Stdin can't be read twice, so it has no hash
+    pub sha256: Option<String>,
+}
+
+/// Calculate the SHA-256 hash of a file, as a lowercase hex string
+pub fn hash_file(path: &Path) -> Result<HashedFile, TidyError> {
+    let sha256 = if is_stdin(path) {
+        None
+    } else {
+        let read_error = |source| TidyError::Read {
+            path: path.to_path_buf(),
+            source,
+        };
+        let mut file = File::open(path).map_err(read_error)?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher).map_err(read_error)?;
+        Some(
+            hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        )
+    };
+    Ok(HashedFile {
+        path: path.display().to_string(),
+        sha256,
+    })
+}
+
+/// Where to write the manifest of an outputted list: next to it, with
+/// ".manifest.json" added to its name.
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use tidy::manifest::manifest_path;
+/// assert_eq!(
+///     manifest_path(Path::new("lists/new_list.txt")),
+///     PathBuf::from("lists/new_list.txt.manifest.json")
+/// );
+/// ```
+pub fn manifest_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".manifest.json");
+    PathBuf::from(path)
+}
+
+/// Write a manifest to a file as (pretty) JSON
+pub fn write_manifest(manifest: &Manifest, path: &Path) -> Result<(), TidyError> {
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| TidyError::Write {
+        path: Some(path.to_path_buf()),
+        source: io::Error::other(e),
+    })?;
+    write_atomically(path, |mut f| writeln!(f, "{}", json))
+}
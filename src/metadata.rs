@@ -0,0 +1,700 @@
+//! Split lines of inputted word lists into the word itself and any metadata
+//! around it (dice rolls, word frequencies, other CSV/TSV columns), so that
+//! Tidy can work on the word and then put the metadata back.
+
+use crate::error::TidyError;
+use crate::parse_delimiter;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::path::PathBuf;
+
+/// The two kinds of tabular files Tidy can read
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TabularFormat {
+    Csv,
+    Tsv,
+}
+
+impl TabularFormat {
+    fn delimiter(&self) -> u8 {
+        match self {
+            TabularFormat::Csv => b',',
+            TabularFormat::Tsv => b'\t',
+        }
+    }
+    /// CSV fields may be quoted (to allow commas in them). TSV files
+    /// are generally not quoted, so we leave any quotation marks alone.
+    fn uses_quotes(&self) -> bool {
+        match self {
+            TabularFormat::Csv => true,
+            TabularFormat::Tsv => false,
+        }
+    }
+}
+
+/// How to find the word in each row of a CSV or TSV file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabularOptions {
+    pub format: TabularFormat,
+    /// Zero-indexed column the word is in
+    pub word_column: usize,
+}
+
+/// A user can specify the column that contains the words either by
+/// number (starting at 1) or by its name in a header row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnSelector {
+    Number(usize),
+    Name(String),
+}
+
+impl fmt::Display for ColumnSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColumnSelector::Number(number) => write!(f, "{}", number),
+            ColumnSelector::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+// Serialized as it'd be typed on the command line, for recipes
+impl Serialize for ColumnSelector {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse user's `--column` input.
+/// ```
+/// use tidy::metadata::{parse_column_selector, ColumnSelector};
+/// assert_eq!(parse_column_selector("2"), Ok(ColumnSelector::Number(2)));
+/// assert_eq!(parse_column_selector("word"), Ok(ColumnSelector::Name("word".to_string())));
+/// assert!(parse_column_selector("0").is_err());
+/// ```
+pub fn parse_column_selector(input: &str) -> Result<ColumnSelector, String> {
+    match input.parse::<usize>() {
+        Ok(0) => Err("Column numbers start at 1.".to_string()),
+        Ok(number) => Ok(ColumnSelector::Number(number)),
+        Err(_) => Ok(ColumnSelector::Name(input.to_string())),
+    }
+}
+
+/// Metadata that was split off of a line, along with what we need to
+/// put it back around the (possibly tidied) word.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Metadata {
+    Before {
+        metadata: String,
+        delimiter: char,
+    },
+    After {
+        metadata: String,
+        delimiter: char,
+    },
+    /// Metadata on both sides of the word, like `11111\tabacus\t0.0012`
+    Both {
+        before: String,
+        before_delimiter: char,
+        after: String,
+        after_delimiter: char,
+    },
+    Tabular {
+        fields: Vec<String>,
+        options: TabularOptions,
+    },
+}
+
+impl Metadata {
+    /// Put metadata back around the given word, producing a line
+    /// in the same shape as the inputted line.
+    pub fn reattach(&self, word: &str) -> String {
+        match self {
+            Metadata::Before {
+                metadata,
+                delimiter,
+            } => metadata.to_owned() + &delimiter.to_string() + word,
+            Metadata::After {
+                metadata,
+                delimiter,
+            } => word.to_owned() + &delimiter.to_string() + metadata,
+            Metadata::Both {
+                before,
+                before_delimiter,
+                after,
+                after_delimiter,
+            } => format!(
+                "{}{}{}{}{}",
+                before, before_delimiter, word, after_delimiter, after
+            ),
+            Metadata::Tabular { fields, options } => {
+                let mut fields = fields.clone();
+                fields[options.word_column] = word.to_string();
+                join_record(&fields, options.format)
+            }
+        }
+    }
+
+    /// Combine this metadata with the metadata of a duplicate word, joining each
+    /// piece (or CSV/TSV field) with a semicolon. Empty pieces, and pieces that
+    /// are already there, aren't added.
+    /// ```
+    /// use tidy::metadata::split_off_metadata;
+    /// let (_word, first) = split_off_metadata("apple\t12", Some('t'), None, None);
+    /// let (_word, second) = split_off_metadata("Apple\t30", Some('t'), None, None);
+    /// let merged = first.unwrap().concatenate(second.unwrap());
+    /// assert_eq!(merged.reattach("apple"), "apple\t12;30");
+    /// ```
+    pub fn concatenate(self, other: Metadata) -> Metadata {
+        match (self, other) {
+            (
+                Metadata::Tabular {
+                    mut fields,
+                    options,
+                },
+                Metadata::Tabular {
+                    fields: other_fields,
+                    ..
+                },
+            ) => {
+                if other_fields.len() > fields.len() {
+                    fields.resize(other_fields.len(), String::new());
+                }
+                for (column, other_field) in other_fields.iter().enumerate() {
+                    if column != options.word_column {
+                        fields[column] = join_pieces(&fields[column], other_field);
+                    }
+                }
+                Metadata::Tabular { fields, options }
+            }
+            // Lines of CSV and TSV files all have tabular metadata, so this
+            // shouldn't happen, but just in case
+            (this @ Metadata::Tabular { .. }, _) | (this, Metadata::Tabular { .. }) => this,
+            (this, other) => {
+                let (before, after) = this.sides();
+                let (other_before, other_after) = other.sides();
+                let join_side =
+                    |side: MetadataSide, other_side: MetadataSide| match (side, other_side) {
+                        (Some((piece, delimiter)), Some((other_piece, _))) => {
+                            Some((join_pieces(&piece, &other_piece), delimiter))
+                        }
+                        (side, other_side) => side.or(other_side),
+                    };
+                match (
+                    join_side(before, other_before),
+                    join_side(after, other_after),
+                ) {
+                    (Some((before, before_delimiter)), Some((after, after_delimiter))) => {
+                        Metadata::Both {
+                            before,
+                            before_delimiter,
+                            after,
+                            after_delimiter,
+                        }
+                    }
+                    (Some((metadata, delimiter)), None) => Metadata::Before {
+                        metadata,
+                        delimiter,
+                    },
+                    (None, Some((metadata, delimiter))) => Metadata::After {
+                        metadata,
+                        delimiter,
+                    },
+                    // Every kind of metadata has at least one side
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// The metadata before and after the word
+    fn sides(self) -> (MetadataSide, MetadataSide) {
+        match self {
+            Metadata::Before {
+                metadata,
+                delimiter,
+            } => (Some((metadata, delimiter)), None),
+            Metadata::After {
+                metadata,
+                delimiter,
+            } => (None, Some((metadata, delimiter))),
+            Metadata::Both {
+                before,
+                before_delimiter,
+                after,
+                after_delimiter,
+            } => (
+                Some((before, before_delimiter)),
+                Some((after, after_delimiter)),
+            ),
+            Metadata::Tabular { .. } => (None, None),
+        }
+    }
+}
+
+/// Metadata on one side of a word, if any, along with its delimiter
+type MetadataSide = Option<(String, char)>;
+
+/// Join two pieces of metadata of duplicate words
+fn join_pieces(piece: &str, other_piece: &str) -> String {
+    if other_piece.is_empty() || piece.split(';').any(|joined| joined == other_piece) {
+        piece.to_string()
+    } else if piece.is_empty() {
+        other_piece.to_string()
+    } else {
+        format!("{};{}", piece, other_piece)
+    }
+}
+
+/// What to do with the metadata of words that end up the same, like "Apple\t12"
+/// and "apple\t30" once lowercased. Only one of the words is kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergePolicy {
+    /// Keep the first word and its metadata
+    KeepFirst,
+    /// Keep the word with the best score (see `ScoreOptions`) and its metadata
+    KeepHighestScore,
+    /// Keep the first word, with the metadata of all of the words joined together
+    Concatenate,
+}
+
+impl fmt::Display for MergePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergePolicy::KeepFirst => write!(f, "keep-first"),
+            MergePolicy::KeepHighestScore => write!(f, "keep-highest-score"),
+            MergePolicy::Concatenate => write!(f, "concatenate"),
+        }
+    }
+}
+
+// Serialized as it'd be typed on the command line, for recipes
+impl Serialize for MergePolicy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Parse user's `--merge` input (case insensitive).
+/// ```
+/// use tidy::metadata::{parse_merge_policy, MergePolicy};
+/// assert_eq!(parse_merge_policy("keep-first"), Ok(MergePolicy::KeepFirst));
+/// assert_eq!(parse_merge_policy("Concat"), Ok(MergePolicy::Concatenate));
+/// assert!(parse_merge_policy("sum").is_err());
+/// ```
+pub fn parse_merge_policy(input: &str) -> Result<MergePolicy, String> {
+    match input.to_lowercase().as_str() {
+        "keep-first" | "first" => Ok(MergePolicy::KeepFirst),
+        "keep-highest-score" | "highest-score" | "best-score" => Ok(MergePolicy::KeepHighestScore),
+        "concatenate" | "concat" => Ok(MergePolicy::Concatenate),
+        _ => Err(format!(
+            "Unknown merge policy {:?}. Options are keep-first, keep-highest-score and concatenate",
+            input
+        )),
+    }
+}
+
+/// If user chose to ignore metadata, split the line into the word and the metadata
+/// based on given delimiter (or CSV/TSV column). Note that metadata may come before
+/// the word, after it, or both. With both, the metadata before the word is split off
+/// first, then the metadata after it.
+/// ```
+/// use tidy::metadata::split_off_metadata;
+/// let (word, metadata) = split_off_metadata("11111\tabacus\t0.0012", Some('t'), Some('t'), None);
+/// assert_eq!(word, "abacus");
+/// assert_eq!(metadata.unwrap().reattach("ABACUS"), "11111\tABACUS\t0.0012");
+/// ```
+///
+/// If no metadata is found, the whole line is treated as the word.
+pub fn split_off_metadata(
+    line: &str,
+    ignore_after_delimiter: Option<char>,
+    ignore_before_delimiter: Option<char>,
+    tabular: Option<TabularOptions>,
+) -> (String, Option<Metadata>) {
+    if let Some(options) = tabular {
+        // Blank lines will be removed later, so no need to warn about them
+        if line.trim().is_empty() {
+            return (String::new(), None);
+        }
+        let fields = parse_record(line, options.format);
+        if fields.len() <= options.word_column {
+            eprintln!(
+                "No column {} found on line: {:?}",
+                options.word_column + 1,
+                line
+            );
+            return (String::new(), None);
+        }
+        return (
+            fields[options.word_column].to_string(),
+            Some(Metadata::Tabular { fields, options }),
+        );
+    }
+    // Parse delimiters. Currently this converts 's' to ' '
+    // and 't' to '\t'.
+    let after_delimiter = ignore_after_delimiter.map(|d| parse_delimiter(d).unwrap());
+    let before_delimiter = ignore_before_delimiter.map(|d| parse_delimiter(d).unwrap());
+    if after_delimiter.is_none() && before_delimiter.is_none() {
+        return (line.to_string(), None);
+    }
+    let (before, rest) = match before_delimiter {
+        Some(delimiter) => match line.split_once(delimiter) {
+            Some((metadata, rest)) => (Some((metadata, delimiter)), rest),
+            None => (None, line),
+        },
+        None => (None, line),
+    };
+    let (word, after) = match after_delimiter {
+        Some(delimiter) => match rest.split_once(delimiter) {
+            Some((word, metadata)) => (word, Some((metadata, delimiter))),
+            None => (rest, None),
+        },
+        None => (rest, None),
+    };
+    // Warn if any metadata we were told to expect is missing
+    if (before_delimiter.is_some() && before.is_none())
+        || (after_delimiter.is_some() && after.is_none())
+    {
+        eprintln!("No metadata found for word: {:?}", line);
+    }
+    let metadata = match (before, after) {
+        (Some((before, before_delimiter)), Some((after, after_delimiter))) => {
+            Some(Metadata::Both {
+                before: before.to_string(),
+                before_delimiter,
+                after: after.to_string(),
+                after_delimiter,
+            })
+        }
+        (Some((metadata, delimiter)), None) => Some(Metadata::Before {
+            metadata: metadata.to_string(),
+            delimiter,
+        }),
+        (None, Some((metadata, delimiter))) => Some(Metadata::After {
+            metadata: metadata.to_string(),
+            delimiter,
+        }),
+        (None, None) => None,
+    };
+    (word.to_string(), metadata)
+}
+
+/// Parse one line of a CSV or TSV file into its fields, respecting quoted
+/// fields in CSV files (so `"Smith, John",12` has two fields).
+/// ```
+/// use tidy::metadata::{parse_record, TabularFormat};
+/// assert_eq!(parse_record("\"Smith, John\",12", TabularFormat::Csv), vec!["Smith, John", "12"]);
+/// assert_eq!(parse_record("abacus\t0.12", TabularFormat::Tsv), vec!["abacus", "0.12"]);
+/// ```
+pub fn parse_record(line: &str, format: TabularFormat) -> Vec<String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(format.delimiter())
+        .quoting(format.uses_quotes())
+        .from_reader(line.as_bytes());
+    match reader.records().next() {
+        Some(Ok(record)) => record.iter().map(|field| field.to_string()).collect(),
+        Some(Err(e)) => {
+            eprintln!("Error parsing line {:?}: {}", line, e);
+            vec![]
+        }
+        None => vec![],
+    }
+}
+
+/// Group the lines of a CSV or TSV file into records, reading them all with one
+/// CSV reader, so that a quoted field with a line break in it (like
+/// `"two\nlines",12`) stays in one record. Each record is returned as one "line",
+/// which `parse_record` can read back.
+/// ```
+/// use tidy::metadata::{group_into_records, parse_record, TabularFormat};
+/// let lines = vec!["\"two".to_string(), "lines\",12".to_string(), "one,3".to_string()];
+/// let records = group_into_records(lines, TabularFormat::Csv).unwrap();
+/// assert_eq!(records.len(), 2);
+/// assert_eq!(parse_record(&records[0], TabularFormat::Csv), vec!["two\nlines", "12"]);
+/// ```
+pub fn group_into_records(
+    lines: Vec<String>,
+    format: TabularFormat,
+) -> Result<Vec<String>, String> {
+    let reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(format.delimiter())
+        .quoting(format.uses_quotes())
+        .from_reader(LinesReader {
+            lines: lines.into_iter(),
+            current_line: vec![],
+            position: 0,
+        });
+    reader
+        .into_records()
+        .map(|record| {
+            let fields: Vec<String> = record
+                .map_err(|e| e.to_string())?
+                .iter()
+                .map(|field| field.to_string())
+                .collect();
+            Ok(join_record(&fields, format))
+        })
+        .collect()
+}
+
+/// Reads lines back as text, each followed by a line break, so that they can
+/// all be given to one CSV reader
+struct LinesReader<I: Iterator<Item = String>> {
+    lines: I,
+    current_line: Vec<u8>,
+    position: usize,
+}
+
+impl<I: Iterator<Item = String>> std::io::Read for LinesReader<I> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.position == self.current_line.len() {
+            match self.lines.next() {
+                Some(line) => {
+                    self.current_line = line.into_bytes();
+                    self.current_line.push(b'\n');
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let length = buf.len().min(self.current_line.len() - self.position);
+        buf[..length].copy_from_slice(&self.current_line[self.position..self.position + length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// Join fields back into one line of a CSV or TSV file, quoting CSV fields
+/// only if necessary.
+/// ```
+/// use tidy::metadata::{join_record, TabularFormat};
+/// let fields = vec!["Smith, John".to_string(), "12".to_string()];
+/// assert_eq!(join_record(&fields, TabularFormat::Csv), "\"Smith, John\",12");
+/// let fields = vec!["say \"hi\"".to_string(), "12".to_string()];
+/// assert_eq!(join_record(&fields, TabularFormat::Csv), "\"say \"\"hi\"\"\",12");
+/// ```
+pub fn join_record(fields: &[String], format: TabularFormat) -> String {
+    let delimiter = format.delimiter() as char;
+    // A record of one empty field is quoted, so it isn't read back as a blank line
+    if format.uses_quotes() && fields.len() == 1 && fields[0].is_empty() {
+        return "\"\"".to_string();
+    }
+    fields
+        .iter()
+        .map(|field| {
+            if format.uses_quotes() && field.contains([delimiter, '"', '\r', '\n']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(&delimiter.to_string())
+}
+
+/// Remove the first row of each inputted tabular file, returning the header row.
+/// The new list only gets one header row, so every file needs the same one
+/// (though files that are empty don't have one). Returns an error if they differ.
+pub fn remove_header_rows(
+    word_lists_by_file: &mut [Vec<String>],
+    filenames: &[PathBuf],
+    format: TabularFormat,
+) -> Result<Option<String>, TidyError> {
+    let mut header_row: Option<String> = None;
+    for (word_list, filename) in word_lists_by_file.iter_mut().zip(filenames) {
+        if word_list.is_empty() {
+            continue;
+        }
+        let this_header_row = word_list.remove(0);
+        match header_row {
+            Some(ref header_row)
+                if parse_record(header_row, format) != parse_record(&this_header_row, format) =>
+            {
+                return Err(TidyError::MalformedFile {
+                    path: filename.to_path_buf(),
+                    reason: format!(
+                        "header row {:?} doesn't match the header row of the first inputted file, {:?}",
+                        this_header_row, header_row
+                    ),
+                });
+            }
+            Some(_) => {}
+            None => header_row = Some(this_header_row),
+        }
+    }
+    Ok(header_row)
+}
+/// Figure out the zero-indexed position of a column (like the one the words are
+/// in). Columns can be given by number, or by name if the files have header rows.
+/// If given more than one file, the column has to be in the same place in all of them.
+///
+/// `option` is the option the user gave the column with (like "--column"), so that
+/// any error can say which one it was.
+pub fn resolve_column(
+    option: &str,
+    column: &ColumnSelector,
+    header_rows: &[String],
+    format: TabularFormat,
+) -> Result<usize, TidyError> {
+    match column {
+        ColumnSelector::Number(number) => Ok(number - 1),
+        ColumnSelector::Name(name) => {
+            if header_rows.is_empty() {
+                return Err(TidyError::invalid_option(
+                    option,
+                    name,
+                    "can't find a column by name without a header row. Use --header or give a column number",
+                ));
+            }
+            let mut positions = vec![];
+            for header_row in header_rows {
+                match parse_record(header_row, format)
+                    .iter()
+                    .position(|field| field.trim() == name)
+                {
+                    Some(position) => positions.push(position),
+                    None => {
+                        return Err(TidyError::invalid_option(
+                            option,
+                            name,
+                            &format!("unable to find this column in header row {:?}", header_row),
+                        ));
+                    }
+                }
+            }
+            positions.dedup();
+            if positions.len() > 1 {
+                Err(TidyError::invalid_option(
+                    option,
+                    name,
+                    "this column is in different places in different inputted files",
+                ))
+            } else {
+                Ok(positions[0])
+            }
+        }
+    }
+}
+
+/// Where to find a numeric score (like a word frequency or a rank) on each
+/// line, and which way round it goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreOptions {
+    /// Zero-indexed field of the line the score is in
+    pub column: usize,
+    /// If true, lower scores are better (like ranks, where 1 is best).
+    /// Otherwise higher scores are better (like frequencies).
+    pub lower_is_better: bool,
+}
+
+/// Find the score on a line. Fields are split by the delimiter of whichever
+/// "ignore" options the user chose, or are the columns of a CSV/TSV file.
+/// Returns `None` if there's no such field or it isn't a number.
+/// ```
+/// use tidy::metadata::{find_score, ScoreOptions};
+/// let score = ScoreOptions { column: 1, lower_is_better: false };
+/// assert_eq!(find_score("abacus\t1234", score, Some('t'), None, None), Some(1234.0));
+/// assert_eq!(find_score("abacus", score, Some('t'), None, None), None);
+/// let score = ScoreOptions { column: 2, lower_is_better: false };
+/// assert_eq!(find_score("11111,abacus 12", score, Some('s'), Some(','), None), Some(12.0));
+/// ```
+pub fn find_score(
+    line: &str,
+    score: ScoreOptions,
+    ignore_after_delimiter: Option<char>,
+    ignore_before_delimiter: Option<char>,
+    tabular: Option<TabularOptions>,
+) -> Option<f64> {
+    let delimiters: Vec<char> = [ignore_after_delimiter, ignore_before_delimiter]
+        .into_iter()
+        .flatten()
+        .map(parse_delimiter)
+        .collect::<Option<_>>()?;
+    let field = match tabular {
+        Some(options) => parse_record(line, options.format)
+            .get(score.column)?
+            .to_string(),
+        // With metadata on both sides, fields are split by either delimiter
+        None if !delimiters.is_empty() => line
+            .split(|c| delimiters.contains(&c))
+            .nth(score.column)?
+            .to_string(),
+        None => return None,
+    };
+    field
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|score| !score.is_nan())
+}
+
+/// Order lines from best score to worst score, so that the best words are
+/// at the front of the list. Lines with the same score keep their order,
+/// and lines without a score go to the back. Also returns how many
+/// (non-blank) lines didn't have a score.
+pub fn order_by_score(
+    lines: Vec<String>,
+    score: ScoreOptions,
+    ignore_after_delimiter: Option<char>,
+    ignore_before_delimiter: Option<char>,
+    tabular: Option<TabularOptions>,
+) -> (Vec<String>, usize) {
+    order_items_by_score(
+        lines,
+        |line| line,
+        score,
+        ignore_after_delimiter,
+        ignore_before_delimiter,
+        tabular,
+    )
+}
+
+/// Like `order_by_score`, but for anything that has a line, like a line along
+/// with its position in the inputted list.
+pub fn order_items_by_score<T, F>(
+    items: Vec<T>,
+    line_of: F,
+    score: ScoreOptions,
+    ignore_after_delimiter: Option<char>,
+    ignore_before_delimiter: Option<char>,
+    tabular: Option<TabularOptions>,
+) -> (Vec<T>, usize)
+where
+    F: Fn(&T) -> &str,
+{
+    let mut scored_items: Vec<(Option<f64>, T)> = items
+        .into_iter()
+        .map(|item| {
+            (
+                find_score(
+                    line_of(&item),
+                    score,
+                    ignore_after_delimiter,
+                    ignore_before_delimiter,
+                    tabular,
+                ),
+                item,
+            )
+        })
+        .collect();
+    let lines_without_scores = scored_items
+        .iter()
+        .filter(|(score, item)| score.is_none() && !line_of(item).trim().is_empty())
+        .count();
+    // sort_by is stable, so ties keep their original order
+    scored_items.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Some(a), Some(b)) if score.lower_is_better => a.total_cmp(b),
+        (Some(a), Some(b)) => b.total_cmp(a),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    (
+        scored_items.into_iter().map(|(_, item)| item).collect(),
+        lines_without_scores,
+    )
+}
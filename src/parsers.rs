@@ -1,16 +1,37 @@
 /// Parse user's input to a handful of options, either directly as a `usize`,
 /// or, if they entered Python exponent notation (base**exponent), which
-/// we'll need to evaluate as an exponent. Either way, return a `usize`
-/// or `expect`/`panic!`.
+/// we'll need to evaluate as an exponent. Numbers can also be multiplied
+/// (like 20*12*10*8), to match a set of different dice. Either way, return
+/// a `usize` or an error.
 ///  
 /// This is useful when making lists fit to a specific amount of dice and
 /// dice sides. (As an example, five rolls of a six-sided dice would be: 6**5).
+/// ```
+/// use tidy::parsers::eval_list_length;
+/// assert_eq!(eval_list_length("20*12*10*8"), Ok(19_200));
+/// assert_eq!(eval_list_length("6**2*4"), Ok(144));
+/// assert!(eval_list_length("6**").is_err());
+/// ```
 pub fn eval_list_length(input: &str) -> Result<usize, String> {
-    match input.split("**").collect::<Vec<&str>>().as_slice() {
-        [] => Err("Please specify a number.".to_string()),
+    // Replace exponents with a character we can tell apart from multiplication
+    input
+        .replace("**", "^")
+        .split('*')
+        .try_fold(1_usize, |product, factor| {
+            let factor = eval_factor(input, factor)?;
+            product
+                .checked_mul(factor)
+                .ok_or_else(|| format!("Input {} is too large.", input))
+        })
+}
+
+/// Evaluate one factor of `eval_list_length`'s input: a number or a base^exponent
+fn eval_factor(input: &str, factor: &str) -> Result<usize, String> {
+    match factor.split('^').collect::<Vec<&str>>().as_slice() {
+        [] | [""] => Err("Please specify a number.".to_string()),
         [num_string] => num_string.parse::<usize>().map_err(|_| {
             format!(
-                "Unable to parse input {}. Enter a number or a base**exponent",
+                "Unable to parse input {}. Enter a number, a base**exponent or numbers multiplied together (like 20*12)",
                 input
             )
         }),
@@ -21,24 +42,55 @@ pub fn eval_list_length(input: &str) -> Result<usize, String> {
             let exponent: u32 = exponent_string
                 .parse::<u32>()
                 .map_err(|_| format!("Unable to parse input {}. Positive integers only.", input))?;
-            Ok(base.pow(exponent))
+            base.checked_pow(exponent)
+                .ok_or_else(|| format!("Input {} is too large.", input))
         }
         _ => Err("You can only specify one exponent! Use format: base**exponent".to_string()),
     }
 }
 
+use crate::list_manipulations::MatchOptions;
+/// Parse user's `--match` input, a comma-separated list of ways to loosen how
+/// words are matched against reject and approved lists.
+/// ```
+/// use tidy::parsers::parse_match_options;
+/// let options = parse_match_options("case-insensitive,diacritic-insensitive").unwrap();
+/// assert!(options.case_insensitive && options.diacritic_insensitive && !options.normalized);
+/// assert!(parse_match_options("fuzzy").is_err());
+/// ```
+pub fn parse_match_options(input: &str) -> Result<MatchOptions, String> {
+    let mut options = MatchOptions::default();
+    for mode in input.split(',') {
+        match mode.trim().to_lowercase().as_str() {
+            "case-insensitive" => options.case_insensitive = true,
+            "normalized" => options.normalized = true,
+            "diacritic-insensitive" => options.diacritic_insensitive = true,
+            _ => {
+                return Err(format!(
+                    "Unknown match mode {}. Please use one or more of the following, separated by commas: case-insensitive, normalized, diacritic-insensitive.",
+                    mode
+                ));
+            }
+        }
+    }
+    Ok(options)
+}
+
 use crate::TidyRequest;
+use crate::error::TidyError;
 use crate::split_and_vectorize;
 pub fn parse_whittle_options(
     mut this_tidy_request: TidyRequest,
     whittle_to_s: Option<String>,
-) -> Result<(TidyRequest, Option<usize>, Option<usize>), String> {
+) -> Result<(TidyRequest, Option<usize>, Option<usize>), TidyError> {
     match whittle_to_s {
         Some(whittle_to_string) => {
             // Some whittle_to String has been provided, which we need to do a lot of work for
             // First, parse length_to_whittle_to
             let length_to_whittle_to =
-                eval_list_length(split_and_vectorize(&whittle_to_string, ",")[0]).unwrap();
+                eval_list_length(split_and_vectorize(&whittle_to_string, ",")[0]).map_err(|e| {
+                    TidyError::invalid_option("--whittle-to", &whittle_to_string, &e)
+                })?;
             // Determine initial starting point
             let starting_point = if split_and_vectorize(&whittle_to_string, ",").len() == 2 {
                 // If user gave us one, use that.
@@ -64,11 +116,14 @@ pub fn parse_whittle_options(
             // Another potential issue: User is asking for too many words, given length of
             // the inputted_word_list (which would be a problem!)
             if length_to_whittle_to > this_tidy_request.list.len() {
-                let error_msg = format!(
-                    "ERROR: Cannot make a list of {} words from the inputted list(s), given the selected options. Please try again, either by changing options or inputting more words.",
-                    length_to_whittle_to
-                );
-                return Err(error_msg);
+                return Err(TidyError::invalid_option(
+                    "--whittle-to",
+                    &whittle_to_string,
+                    &format!(
+                        "cannot make a list of {} words from the inputted list(s), given the selected options. Please try again, either by changing options or inputting more words",
+                        length_to_whittle_to
+                    ),
+                ));
             }
 
             // Give user a heads up that we're working on it.
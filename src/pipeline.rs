@@ -0,0 +1,677 @@
+//! Each of the operations Tidy can perform on a list is a "stage". `tidy_list`
+//! builds a pipeline of stages from a `TidyRequest` and runs them one after
+//! another. By default the stages run in the order listed in `STAGE_NAMES`, but
+//! users can choose their own order (the `--order` option), since different
+//! orders can give quite different lists.
+//!
+//! Library users can also build their own pipelines, including stages of their
+//! own, and run them with `run_pipeline`.
+//!
+//! To find out which stage removed which word (and why), use
+//! `run_pipeline_with_removals` instead.
+
+use crate::TidyRequest;
+use crate::count_characters;
+use crate::edit_distance::find_edit_distance;
+use crate::error::TidyError;
+use crate::list_manipulations::*;
+use crate::metadata::Metadata;
+use crate::parse_delimiter;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+
+/// A word on the list, along with any metadata that was split off of its line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub word: String,
+    pub metadata: Option<Metadata>,
+    /// Position (starting at 0) of the line this word came from in the inputted list
+    pub source: usize,
+}
+
+impl Entry {
+    /// The whole line of this entry, with any metadata re-added to the word
+    pub fn line(&self) -> String {
+        match self.metadata {
+            Some(ref metadata) => metadata.reattach(&self.word).trim().to_string(),
+            None => self.word.to_string(),
+        }
+    }
+}
+
+/// One operation on a list, like lowercasing every word or removing prefix words
+pub trait Stage {
+    /// The name of this stage, as used with `--order`
+    fn name(&self) -> &str;
+    /// Run this stage on the given entries, returning the entries that remain
+    fn apply(&self, entries: Vec<Entry>) -> Vec<Entry>;
+    /// Explain why this stage removed the given word, given the words it kept and
+    /// the words it removed. Stages that compare words to each other should name
+    /// the word that this word conflicted with.
+    fn explain_removal(
+        &self,
+        _word: &str,
+        _kept: &WordIndex,
+        _removed: &WordIndex,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// A set of words, with lookups for explaining why a stage removed a word
+/// that don't need to search through every word. The sorted lists these
+/// lookups use are only built the first time they're needed.
+pub struct WordIndex<'a> {
+    words: Vec<&'a str>,
+    set: HashSet<&'a str>,
+    sorted: OnceCell<Vec<&'a str>>,
+    /// Each word reversed (to find words by suffix), with the word itself
+    sorted_reversed: OnceCell<Vec<(String, &'a str)>>,
+    by_length: OnceCell<HashMap<usize, Vec<&'a str>>>,
+}
+
+impl<'a> WordIndex<'a> {
+    pub fn new(words: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut set = HashSet::new();
+        let words = words.into_iter().filter(|word| set.insert(*word)).collect();
+        WordIndex {
+            words,
+            set,
+            sorted: OnceCell::new(),
+            sorted_reversed: OnceCell::new(),
+            by_length: OnceCell::new(),
+        }
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.set.contains(word)
+    }
+
+    /// A word (other than the given one) that starts with the given prefix
+    /// ```
+    /// use tidy::pipeline::WordIndex;
+    /// let index = WordIndex::new(["news", "newspaper", "paper"]);
+    /// assert_eq!(index.starting_with("news", "news"), Some("newspaper"));
+    /// assert_eq!(index.starting_with("paper", "paper"), None);
+    /// ```
+    pub fn starting_with(&self, prefix: &str, other_than: &str) -> Option<&'a str> {
+        let sorted = self.sorted.get_or_init(|| {
+            let mut sorted = self.words.clone();
+            sorted.sort_unstable();
+            sorted
+        });
+        // Words starting with the prefix are all together in the sorted list
+        let start = sorted.partition_point(|word| *word < prefix);
+        sorted[start..]
+            .iter()
+            .take_while(|word| word.starts_with(prefix))
+            .find(|word| **word != other_than)
+            .copied()
+    }
+
+    /// A word (other than the given one) that ends with the given suffix
+    pub fn ending_with(&self, suffix: &str, other_than: &str) -> Option<&'a str> {
+        let sorted_reversed = self.sorted_reversed.get_or_init(|| {
+            let mut sorted_reversed: Vec<(String, &str)> = self
+                .words
+                .iter()
+                .map(|word| (word.chars().rev().collect(), *word))
+                .collect();
+            sorted_reversed.sort_unstable();
+            sorted_reversed
+        });
+        let reversed_suffix: String = suffix.chars().rev().collect();
+        let start = sorted_reversed.partition_point(|(reversed, _)| *reversed < reversed_suffix);
+        sorted_reversed[start..]
+            .iter()
+            .take_while(|(reversed, _)| reversed.starts_with(&reversed_suffix))
+            .map(|(_, word)| *word)
+            .find(|word| *word != other_than)
+    }
+
+    /// Words that are within the given number of characters of the given length
+    pub fn with_length_near(&self, length: usize, within: usize) -> impl Iterator<Item = &'a str> {
+        let by_length = self.by_length.get_or_init(|| {
+            let mut by_length: HashMap<usize, Vec<&str>> = HashMap::new();
+            for word in &self.words {
+                by_length
+                    .entry(count_characters(word))
+                    .or_default()
+                    .push(word);
+            }
+            by_length
+        });
+        (length.saturating_sub(within)..=length + within)
+            .filter_map(|length| by_length.get(&length))
+            .flatten()
+            .copied()
+    }
+}
+
+/// A word that a stage removed from the list, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct Removal {
+    /// The word as it was when it was removed
+    pub word: String,
+    /// Position (starting at 0) of the line this word came from in the inputted list
+    pub source: usize,
+    /// Name of the stage that removed the word
+    pub stage: String,
+    pub reason: Option<String>,
+}
+
+impl Removal {
+    /// The removal of a word because it's the same as a word that was kept
+    pub fn duplicate(entry: Entry, kept: &Entry) -> Self {
+        let same = if entry.line() == kept.line() {
+            "line"
+        } else {
+            "word"
+        };
+        Removal {
+            word: entry.word,
+            source: entry.source,
+            stage: "duplicate".to_string(),
+            reason: Some(format!("same {} as line {}", same, kept.source + 1)),
+        }
+    }
+}
+
+/// A stage that changes each word on its own, like lowercasing it.
+/// Words are trimmed afterward, and any that end up empty are removed.
+pub struct ModifyWords<F: Fn(&str) -> String> {
+    pub name: &'static str,
+    pub modify: F,
+}
+
+impl<F: Fn(&str) -> String> Stage for ModifyWords<F> {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn apply(&self, entries: Vec<Entry>) -> Vec<Entry> {
+        entries
+            .into_iter()
+            .map(|entry| Entry {
+                word: (self.modify)(&entry.word).trim().to_string(),
+                ..entry
+            })
+            .filter(|entry| !entry.word.is_empty())
+            .collect()
+    }
+    fn explain_removal(
+        &self,
+        _word: &str,
+        _kept: &WordIndex,
+        _removed: &WordIndex,
+    ) -> Option<String> {
+        Some("nothing left of word".to_string())
+    }
+}
+
+/// A stage that decides whether to keep each word on its own, like
+/// removing words that are too long.
+pub struct FilterWords<F: Fn(&str) -> bool> {
+    pub name: &'static str,
+    /// Returns true if the word should be kept
+    pub keep: F,
+}
+
+impl<F: Fn(&str) -> bool> Stage for FilterWords<F> {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn apply(&self, mut entries: Vec<Entry>) -> Vec<Entry> {
+        entries.retain(|entry| (self.keep)(&entry.word));
+        entries
+    }
+}
+
+/// A stage that needs to look at the whole list at once, like removing
+/// prefix words. These work on the words alone, keeping each word's
+/// metadata to one side, so that metadata doesn't get in the way of
+/// comparing words.
+pub struct RemoveFromList<F, E>
+where
+    F: Fn(Vec<String>) -> Vec<String>,
+    E: Fn(&str, &WordIndex, &WordIndex) -> Option<String>,
+{
+    pub name: &'static str,
+    /// Given the list of words, returns the words to keep
+    pub remove_words: F,
+    /// Explains why a word was removed (see `Stage::explain_removal`)
+    pub explain: E,
+}
+
+impl<F, E> Stage for RemoveFromList<F, E>
+where
+    F: Fn(Vec<String>) -> Vec<String>,
+    E: Fn(&str, &WordIndex, &WordIndex) -> Option<String>,
+{
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn apply(&self, mut entries: Vec<Entry>) -> Vec<Entry> {
+        // Only give the stage each word once, so that a word isn't compared to
+        // itself. Lines with the same word (say, with different metadata) are kept
+        // or removed together, and any duplicates are dealt with after the stages.
+        let mut seen_words = HashSet::new();
+        let words = entries
+            .iter()
+            .filter(|entry| seen_words.insert(entry.word.as_str()))
+            .map(|entry| entry.word.to_string())
+            .collect();
+        let surviving_words: HashSet<String> = (self.remove_words)(words).into_iter().collect();
+        entries.retain(|entry| surviving_words.contains(&entry.word));
+        entries
+    }
+    fn explain_removal(&self, word: &str, kept: &WordIndex, removed: &WordIndex) -> Option<String> {
+        (self.explain)(word, kept, removed)
+    }
+}
+
+/// Names of all the stages Tidy can build from a `TidyRequest`, in the order
+/// they run by default. Word modifications come first, then removals of
+/// individual words, then the stages that look at the whole list.
+pub const STAGE_NAMES: [&str; 21] = [
+    "delete-before",
+    "delete-after",
+    "delete-integers",
+    "delete-nonalphanumeric",
+    "lowercase",
+    "straighten",
+    "remove-nonascii",
+    "remove-nonalphanumeric",
+    "remove-nonalphabetic",
+    "remove-non-latin-alphabetic",
+    "remove-integers",
+    "reject",
+    "approve",
+    "minimum-word-length",
+    "maximum-word-length",
+    "homophones",
+    "shared-prefix-length",
+    "minimum-edit-distance",
+    "remove-suffix",
+    "remove-prefix",
+    "schlinkert-prune",
+];
+
+/// Run each stage on the list in turn
+pub fn run_pipeline(entries: Vec<Entry>, stages: &[Box<dyn Stage>]) -> Vec<Entry> {
+    stages
+        .iter()
+        .fold(entries, |entries, stage| stage.apply(entries))
+}
+
+/// Like `run_pipeline`, but also keep track of which stage removed which
+/// word, and why.
+pub fn run_pipeline_with_removals(
+    mut entries: Vec<Entry>,
+    stages: &[Box<dyn Stage>],
+) -> (Vec<Entry>, Vec<Removal>) {
+    let mut removals = vec![];
+    for stage in stages {
+        let before: Vec<(usize, String)> = entries
+            .iter()
+            .map(|entry| (entry.source, entry.word.to_string()))
+            .collect();
+        entries = stage.apply(entries);
+        let surviving_sources: HashSet<usize> = entries.iter().map(|entry| entry.source).collect();
+        let removed: Vec<(usize, String)> = before
+            .into_iter()
+            .filter(|(source, _word)| !surviving_sources.contains(source))
+            .collect();
+        if removed.is_empty() {
+            continue;
+        }
+        // Where each kept word first appears on the list
+        let mut kept_sources: HashMap<&str, usize> = HashMap::new();
+        for entry in &entries {
+            kept_sources.entry(&entry.word).or_insert(entry.source);
+        }
+        let kept_words = WordIndex::new(entries.iter().map(|entry| entry.word.as_str()));
+        let removed_words = WordIndex::new(removed.iter().map(|(_source, word)| word.as_str()));
+        for (source, word) in &removed {
+            // A stage may remove one copy of a word and keep another
+            let removal = match kept_sources.get(word.as_str()) {
+                Some(kept_source) => Removal {
+                    word: word.to_string(),
+                    source: *source,
+                    stage: "duplicate".to_string(),
+                    reason: Some(format!("same word as line {}", kept_source + 1)),
+                },
+                None => Removal {
+                    word: word.to_string(),
+                    source: *source,
+                    stage: stage.name().to_string(),
+                    reason: stage.explain_removal(word, &kept_words, &removed_words),
+                },
+            };
+            removals.push(removal);
+        }
+    }
+    (entries, removals)
+}
+
+/// Build the stages the given request asks for, in the default order
+pub fn build_stages(req: &TidyRequest) -> Vec<Box<dyn Stage>> {
+    let mut stages: Vec<Box<dyn Stage>> = vec![];
+
+    // WORD MODIFICATIONS
+    if let Some(delimiter) = req.should_delete_before_first_delimiter {
+        let delimiter = parse_delimiter(delimiter).unwrap();
+        stages.push(Box::new(ModifyWords {
+            name: "delete-before",
+            modify: move |word: &str| delete_before_first_char(word, delimiter).to_string(),
+        }));
+    }
+    if let Some(delimiter) = req.should_delete_after_first_delimiter {
+        let delimiter = parse_delimiter(delimiter).unwrap();
+        stages.push(Box::new(ModifyWords {
+            name: "delete-after",
+            modify: move |word: &str| delete_after_first_char(word, delimiter).to_string(),
+        }));
+    }
+    if req.should_delete_integers {
+        stages.push(Box::new(ModifyWords {
+            name: "delete-integers",
+            modify: |word: &str| {
+                if word.chars().any(|c| c.is_numeric()) {
+                    delete_integers(word.to_string())
+                } else {
+                    word.to_string()
+                }
+            },
+        }));
+    }
+    if req.should_delete_nonalphanumeric {
+        stages.push(Box::new(ModifyWords {
+            name: "delete-nonalphanumeric",
+            // Leave words that are entirely non-alphanumeric alone, rather
+            // than deleting them completely
+            modify: |word: &str| {
+                if word.chars().any(|c| c.is_alphanumeric()) {
+                    delete_nonalphanumeric(word.to_string())
+                } else {
+                    word.to_string()
+                }
+            },
+        }));
+    }
+    if req.to_lowercase {
+        stages.push(Box::new(ModifyWords {
+            name: "lowercase",
+            modify: |word: &str| word.to_ascii_lowercase(),
+        }));
+    }
+    if req.should_straighten_quotes {
+        stages.push(Box::new(ModifyWords {
+            name: "straighten",
+            modify: |word: &str| straighten_quotes(word).to_string(),
+        }));
+    }
+
+    // WORD REMOVALS
+    if req.should_remove_nonascii {
+        stages.push(Box::new(FilterWords {
+            name: "remove-nonascii",
+            keep: |word: &str| word.is_ascii(),
+        }));
+    }
+    if req.should_remove_nonalphanumeric {
+        stages.push(Box::new(FilterWords {
+            name: "remove-nonalphanumeric",
+            keep: |word: &str| word.chars().all(|c| c.is_alphanumeric()),
+        }));
+    }
+    if req.should_remove_nonalphabetic {
+        stages.push(Box::new(FilterWords {
+            name: "remove-nonalphabetic",
+            keep: |word: &str| word.chars().all(|c| c.is_alphabetic()),
+        }));
+    }
+    if req.should_remove_non_latin_alphabetic {
+        stages.push(Box::new(FilterWords {
+            name: "remove-non-latin-alphabetic",
+            keep: |word: &str| word.chars().all(|chr| is_latin_alphabetic(chr as u16)),
+        }));
+    }
+    if req.should_remove_integers {
+        stages.push(Box::new(FilterWords {
+            name: "remove-integers",
+            keep: |word: &str| !word.chars().any(|c| c.is_numeric()),
+        }));
+    }
+    if let Some(reject_list) = req.reject_list.clone() {
+        stages.push(Box::new(FilterWords {
+            name: "reject",
+            keep: move |word: &str| !reject_list.contains(word),
+        }));
+    }
+    if let Some(approved_list) = req.approved_list.clone() {
+        stages.push(Box::new(FilterWords {
+            name: "approve",
+            keep: move |word: &str| approved_list.contains(word),
+        }));
+    }
+    if let Some(minimum_length) = req.minimum_length {
+        stages.push(Box::new(FilterWords {
+            name: "minimum-word-length",
+            keep: move |word: &str| count_characters(word) >= minimum_length,
+        }));
+    }
+    if let Some(maximum_length) = req.maximum_length {
+        stages.push(Box::new(FilterWords {
+            name: "maximum-word-length",
+            keep: move |word: &str| count_characters(word) <= maximum_length,
+        }));
+    }
+
+    // WHOLE-LIST REMOVALS
+    // If the list is ordered by score, prefer keeping the words with better scores
+    // (which come first) when choosing between words.
+    let prefer_earlier_words = req.score.is_some();
+    if let Some(homophones_list) = req.homophones_list.clone() {
+        // Which group of homophones each word is in
+        let mut homophone_groups: HashMap<String, Vec<String>> = HashMap::new();
+        for group in &homophones_list {
+            for homophone in group {
+                homophone_groups
+                    .entry(homophone.to_string())
+                    .or_insert_with(|| group.clone());
+            }
+        }
+        stages.push(Box::new(RemoveFromList {
+            name: "homophones",
+            remove_words: move |words| {
+                if prefer_earlier_words {
+                    remove_homophones_by_preference(words, homophones_list.clone())
+                } else {
+                    remove_homophones(words, homophones_list.clone())
+                }
+            },
+            explain: move |word, kept, _removed| {
+                let kept_homophone = homophone_groups
+                    .get(word)?
+                    .iter()
+                    .find(|homophone| *homophone != word && kept.contains(homophone))?;
+                Some(format!("homophone of {}", kept_homophone))
+            },
+        }));
+    }
+    if let Some(maximum_shared_prefix_length) = req.maximum_shared_prefix_length {
+        stages.push(Box::new(RemoveFromList {
+            name: "shared-prefix-length",
+            remove_words: move |words| {
+                if prefer_earlier_words {
+                    guarantee_maximum_prefix_length_by_preference(
+                        &words,
+                        maximum_shared_prefix_length,
+                    )
+                } else {
+                    guarantee_maximum_prefix_length(&words, maximum_shared_prefix_length)
+                }
+            },
+            explain: move |word, kept, _removed| {
+                if count_characters(word) < maximum_shared_prefix_length {
+                    return Some(format!(
+                        "shorter than {} characters",
+                        maximum_shared_prefix_length
+                    ));
+                }
+                let prefix = get_prefix(word, maximum_shared_prefix_length);
+                let kept_word = kept.starting_with(&prefix, word)?;
+                Some(format!(
+                    "shares first {} characters with {}",
+                    maximum_shared_prefix_length, kept_word
+                ))
+            },
+        }));
+    }
+    if let Some(minimum_edit_distance) = req.minimum_edit_distance {
+        stages.push(Box::new(RemoveFromList {
+            name: "minimum-edit-distance",
+            remove_words: move |words| enfore_minimum_edit_distance(words, minimum_edit_distance),
+            explain: move |word, kept, removed| {
+                // Words whose lengths differ by the minimum distance or more can't be
+                // too close, so only look at words with lengths near this word's
+                let nearby_words = |words: &WordIndex<'_>| -> Option<String> {
+                    words
+                        .with_length_near(
+                            count_characters(word),
+                            minimum_edit_distance.saturating_sub(1),
+                        )
+                        .find(|other| {
+                            *other != word
+                                && (find_edit_distance(word, other) as usize)
+                                    < minimum_edit_distance
+                        })
+                        .map(|other| other.to_string())
+                };
+                // Prefer naming a word that's still on the list
+                let close_word = nearby_words(kept).or_else(|| nearby_words(removed))?;
+                Some(format!(
+                    "edit distance {} from {}",
+                    find_edit_distance(word, &close_word),
+                    close_word
+                ))
+            },
+        }));
+    }
+    if req.should_remove_suffix_words {
+        stages.push(Box::new(RemoveFromList {
+            name: "remove-suffix",
+            remove_words: remove_suffix_words,
+            explain: |word, kept, removed| {
+                let longer_word = kept
+                    .ending_with(word, word)
+                    .or_else(|| removed.ending_with(word, word))?;
+                Some(format!("suffix of {}", longer_word))
+            },
+        }));
+    }
+    if req.should_remove_prefix_words {
+        stages.push(Box::new(RemoveFromList {
+            name: "remove-prefix",
+            remove_words: move |words| {
+                if prefer_earlier_words {
+                    remove_prefix_words_by_preference(words)
+                } else {
+                    remove_prefix_words(words)
+                }
+            },
+            explain: |word, kept, removed| {
+                // Usually the prefix word is removed, but if preferring earlier words,
+                // the longer word may be removed instead
+                match kept
+                    .starting_with(word, word)
+                    .or_else(|| removed.starting_with(word, word))
+                {
+                    Some(longer_word) => Some(format!("prefix of {}", longer_word)),
+                    None => word
+                        .char_indices()
+                        .skip(1)
+                        .map(|(end, _)| &word[..end])
+                        .find(|prefix_word| kept.contains(prefix_word))
+                        .map(|prefix_word| format!("has prefix word {}", prefix_word)),
+                }
+            },
+        }));
+    }
+    if req.should_schlinkert_prune {
+        stages.push(Box::new(RemoveFromList {
+            name: "schlinkert-prune",
+            remove_words: |words: Vec<String>| schlinkert_prune(&words),
+            explain: |_word, _kept, _removed| {
+                Some("removed to make the list uniquely decodable".to_string())
+            },
+        }));
+    }
+    stages
+}
+
+/// Reorder stages so that the named stages run in the given order. Each named
+/// stage takes one of the places that the named stages would run by default,
+/// and stages that aren't named stay where they are. So an order of
+/// "schlinkert-prune,minimum-edit-distance" swaps just those two stages.
+///
+/// Returns an error if a name isn't one of the stages, or if the request
+/// doesn't use that stage.
+/// ```
+/// use tidy::TidyRequest;
+/// use tidy::pipeline::{build_stages, order_stages};
+/// let req = TidyRequest {
+///     to_lowercase: true,
+///     should_remove_prefix_words: true,
+///     should_schlinkert_prune: true,
+///     ..Default::default()
+/// };
+/// let order = vec!["schlinkert-prune".to_string(), "remove-prefix".to_string()];
+/// let stages = order_stages(build_stages(&req), &order).unwrap();
+/// let names: Vec<&str> = stages.iter().map(|stage| stage.name()).collect();
+/// assert_eq!(names, vec!["lowercase", "schlinkert-prune", "remove-prefix"]);
+/// ```
+pub fn order_stages(
+    stages: Vec<Box<dyn Stage>>,
+    order: &[String],
+) -> Result<Vec<Box<dyn Stage>>, TidyError> {
+    let invalid_order =
+        |reason: &str| TidyError::invalid_option("--order", &order.join(","), reason);
+    for (i, name) in order.iter().enumerate() {
+        if order[..i].contains(name) {
+            return Err(invalid_order(&format!("stage {:?} is given twice", name)));
+        }
+        if !stages.iter().any(|stage| stage.name() == name) {
+            return Err(invalid_order(&if STAGE_NAMES.contains(&name.as_str()) {
+                format!(
+                    "stage {:?} isn't being used. Use the --{} option to use it",
+                    name, name
+                )
+            } else {
+                format!(
+                    "unknown stage {:?}. Stages are: {}",
+                    name,
+                    STAGE_NAMES.join(", ")
+                )
+            }));
+        }
+    }
+    // Take the named stages out of the pipeline, leaving gaps...
+    let mut named_stages: Vec<Option<Box<dyn Stage>>> = order.iter().map(|_| None).collect();
+    let mut slots: Vec<Option<Box<dyn Stage>>> = vec![];
+    for stage in stages {
+        match order.iter().position(|name| name == stage.name()) {
+            Some(position) => {
+                named_stages[position] = Some(stage);
+                slots.push(None);
+            }
+            None => slots.push(Some(stage)),
+        }
+    }
+    // ...then fill the gaps with the named stages, in the order given
+    let mut named_stages = named_stages.into_iter().flatten();
+    Ok(slots
+        .into_iter()
+        .filter_map(|slot| slot.or_else(|| named_stages.next()))
+        .collect())
+}
@@ -0,0 +1,253 @@
+//! Recipes let users save the options of a Tidy run to a file (TOML or JSON)
+//! and use them again later with `--recipe`, rather than keeping long
+//! commands in shell scripts.
+//!
+//! Each key of a recipe is the long name of an option (e.g. `minimum-word-length`),
+//! and the inputted word lists go under `inputs`. For example:
+//!
+//! ```toml
+//! inputs = ["wiki_frequency_list.txt"]
+//! lowercase = true
+//! remove-prefix = true
+//! minimum-word-length = 3
+//! reject = ["profane_words.txt", "names.txt"]
+//! attributes = 2
+//! ```
+//!
+//! Options given on the command line override the same options in a recipe.
+//! Relative paths in a recipe are relative to the recipe file's directory.
+
+use crate::error::TidyError;
+use crate::file_readers::is_stdin;
+use crate::file_writer::write_atomically;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::any::TypeId;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Inputted word lists don't have a long option name, so they get this key
+const INPUTS_KEY: &str = "inputs";
+/// Options about recipes themselves (and help and version) don't belong in recipes
+const NOT_IN_RECIPES: [&str; 4] = ["recipe", "save-recipe", "help", "version"];
+
+/// A recipe is a map of option names to their values
+pub type Recipe = Map<String, Value>;
+
+fn recipe_key(arg: &Arg) -> &str {
+    arg.get_long().unwrap_or(INPUTS_KEY)
+}
+
+fn is_allowed_in_recipes(arg: &Arg) -> bool {
+    !NOT_IN_RECIPES.contains(&recipe_key(arg))
+}
+
+fn is_path(arg: &Arg) -> bool {
+    arg.get_value_parser().type_id() == TypeId::of::<PathBuf>()
+}
+
+/// The directory that relative paths in the given recipe file are relative to
+fn recipe_directory(recipe_path: &Path) -> &Path {
+    recipe_path.parent().unwrap_or(Path::new(""))
+}
+
+/// Change each path in a value of a recipe (other than "-", for stdin)
+fn map_paths(value: &mut Value, change: &dyn Fn(&Path) -> PathBuf) {
+    match value {
+        Value::String(path) if !is_stdin(Path::new(path)) => {
+            *path = change(Path::new(path)).to_string_lossy().to_string();
+        }
+        Value::Array(values) => {
+            for value in values {
+                map_paths(value, change);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// JSON recipes need to end in ".json". Anything else is read (and written) as TOML.
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+/// Read a recipe from a TOML or JSON file
+pub fn read_recipe(path: &Path) -> Result<Recipe, TidyError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| TidyError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_recipe(&contents, is_json(path)).map_err(|reason| TidyError::MalformedFile {
+        path: path.to_path_buf(),
+        reason,
+    })
+}
+
+/// Parse the contents of a recipe file, which may be TOML or JSON
+pub fn parse_recipe(contents: &str, is_json: bool) -> Result<Recipe, String> {
+    if is_json {
+        serde_json::from_str(contents).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+}
+
+/// Write a recipe to a file, as JSON if the path ends in ".json", otherwise as TOML.
+/// Paths in the recipe are rewritten to be relative to the recipe file's directory
+/// where possible (and absolute otherwise), so the recipe works from anywhere.
+pub fn write_recipe(recipe: &Recipe, command: &Command, path: &Path) -> Result<(), TidyError> {
+    let write_error = |source| TidyError::Write {
+        path: Some(path.to_path_buf()),
+        source,
+    };
+    let recipe_directory = match recipe_directory(path) {
+        directory if directory.as_os_str().is_empty() => std::env::current_dir(),
+        directory => std::path::absolute(directory),
+    }
+    .map_err(write_error)?;
+    let mut recipe = recipe.clone();
+    for arg in command.get_arguments().filter(|arg| is_path(arg)) {
+        if let Some(value) = recipe.get_mut(recipe_key(arg)) {
+            map_paths(value, &|path| match std::path::absolute(path) {
+                Ok(absolute_path) => match absolute_path.strip_prefix(&recipe_directory) {
+                    Ok(relative_path) => relative_path.to_path_buf(),
+                    Err(_) => absolute_path,
+                },
+                Err(_) => path.to_path_buf(),
+            });
+        }
+    }
+    let contents = if is_json(path) {
+        serde_json::to_string_pretty(&recipe).map_err(std::io::Error::other)
+    } else {
+        toml::to_string(&recipe).map_err(std::io::Error::other)
+    };
+    let contents = contents.map_err(write_error)?;
+    write_atomically(path, |mut f| f.write_all(contents.as_bytes()))
+}
+
+/// Turn a single value of a recipe into a string, like it'd be typed on the command line
+fn value_to_argument(key: &str, value: &Value) -> Result<String, TidyError> {
+    match value {
+        Value::String(string) => Ok(string.to_string()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(boolean) => Ok(boolean.to_string()),
+        _ => Err(invalid_recipe_value(
+            key,
+            value,
+            "unable to use this value in a recipe",
+        )),
+    }
+}
+
+fn invalid_recipe_value(key: &str, value: &Value, reason: &str) -> TidyError {
+    TidyError::invalid_option(&format!("--{}", key), &value.to_string(), reason)
+}
+
+/// Turn a recipe (read from the given path) into command-line arguments, skipping any
+/// options that the user already gave on the command line (so that the command line
+/// overrides the recipe).
+///
+/// Returns the options and the inputted word lists separately, as inputted word lists
+/// need to go at the end of the command line.
+pub fn recipe_to_arguments(
+    recipe: &Recipe,
+    recipe_path: &Path,
+    command: &Command,
+    command_line_matches: &ArgMatches,
+) -> Result<(Vec<String>, Vec<String>), TidyError> {
+    let mut options = vec![];
+    let mut inputs = vec![];
+    for (key, value) in recipe {
+        let arg = match command
+            .get_arguments()
+            .find(|arg| recipe_key(arg) == key && is_allowed_in_recipes(arg))
+        {
+            Some(arg) => arg,
+            None => return Err(invalid_recipe_value(key, value, "unknown option in recipe")),
+        };
+        if command_line_matches.value_source(arg.get_id().as_str())
+            == Some(ValueSource::CommandLine)
+        {
+            continue;
+        }
+        let mut value = value.clone();
+        if is_path(arg) {
+            map_paths(&mut value, &|path| recipe_directory(recipe_path).join(path));
+        }
+        let value = &value;
+        let values = match value {
+            Value::Array(values) => values
+                .iter()
+                .map(|value| value_to_argument(key, value))
+                .collect::<Result<Vec<String>, TidyError>>()?,
+            value => vec![value_to_argument(key, value)?],
+        };
+        if arg.is_positional() {
+            inputs.extend(values);
+            continue;
+        }
+        match (arg.get_action(), value) {
+            (ArgAction::SetTrue, Value::Bool(true)) => options.push(format!("--{}", key)),
+            (ArgAction::SetTrue, Value::Bool(false)) => {}
+            (ArgAction::SetTrue, _) => {
+                return Err(invalid_recipe_value(key, value, "should be true or false"));
+            }
+            (ArgAction::Count, Value::Number(number)) => match number.as_u64() {
+                Some(count) => {
+                    for _ in 0..count {
+                        options.push(format!("--{}", key));
+                    }
+                }
+                None => return Err(invalid_recipe_value(key, value, "should be a count")),
+            },
+            // Using "=" means values that start with a dash (like "-" for stdin)
+            // aren't mistaken for options
+            _ => {
+                for value in values {
+                    options.push(format!("--{}={}", key, value));
+                }
+            }
+        }
+    }
+    Ok((options, inputs))
+}
+
+/// Make a recipe of all the options the user gave, whether on the command line or
+/// from a recipe, taking their values from the parsed `options` (so that each value
+/// keeps its type, like `minimum-word-length = 3` but `comment-prefix = "007"`).
+/// Options left at their defaults are left out.
+pub fn effective_recipe<T: Serialize>(
+    options: &T,
+    command: &Command,
+    matches: &ArgMatches,
+) -> Result<Recipe, TidyError> {
+    let options = match serde_json::to_value(options) {
+        Ok(Value::Object(options)) => options,
+        Ok(_) => Recipe::new(),
+        Err(e) => {
+            return Err(TidyError::invalid_option(
+                "--save-recipe",
+                "",
+                &format!("unable to put options in a recipe: {}", e),
+            ));
+        }
+    };
+    let mut recipe = Recipe::new();
+    for arg in command
+        .get_arguments()
+        .filter(|arg| is_allowed_in_recipes(arg))
+    {
+        let id = arg.get_id().as_str();
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        if let Some(value) = options.get(id) {
+            recipe.insert(recipe_key(arg).to_string(), value.clone());
+        }
+    }
+    Ok(recipe)
+}
@@ -0,0 +1,69 @@
+mod diff_tests {
+    use tidy::diff::*;
+
+    fn make_lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn can_find_added_removed_and_kept_words() {
+        let old_list = make_lines(&["apple", "banana", "cherry", ""]);
+        let new_list = make_lines(&["apple", "cherry", "date"]);
+        let diff = diff_lists(&old_list, &new_list, 1).unwrap();
+        assert_eq!(diff.added, vec!["date"]);
+        assert_eq!(diff.removed, vec!["banana"]);
+        assert_eq!(diff.kept, vec!["apple", "cherry"]);
+        assert!(diff.changed_codes.is_empty());
+        assert_eq!(diff.old_attributes.list_length, 3);
+        assert_eq!(diff.new_attributes.list_length, 3);
+    }
+
+    #[test]
+    fn can_diff_lists_of_long_words() {
+        let old_list = make_lines(&["encyclopedia", "fundamentally"]);
+        let new_list = make_lines(&["11111\tencyclopedia", "11112\tfundamentally"]);
+        let diff = diff_lists(&old_list, &old_list, 0).unwrap();
+        assert_eq!(diff.kept.len(), 2);
+        assert!(diff.old_attributes.is_above_brute_force_line);
+        assert!(diff_lists(&new_list, &old_list, 0).is_ok());
+    }
+
+    #[test]
+    fn can_diff_a_list_with_codes_against_one_without() {
+        let old_list = make_lines(&["11\tcat", "12\tdog"]);
+        let new_list = make_lines(&["cat", "dog", "eel"]);
+        let diff = diff_lists(&old_list, &new_list, 0).unwrap();
+        assert_eq!(diff.added, vec!["eel"]);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.kept, vec!["cat", "dog"]);
+        assert!(diff.changed_codes.is_empty());
+    }
+
+    #[test]
+    fn can_find_dice_codes_that_changed_meaning() {
+        let old_list = make_lines(&["11\tapple", "12\tbanana", "13\tcherry"]);
+        let new_list = make_lines(&["11\tapple", "12\tcherry", "13\tdate", "14\telder"]);
+        let diff = diff_lists(&old_list, &new_list, 0).unwrap();
+        assert_eq!(diff.added, vec!["date", "elder"]);
+        assert_eq!(diff.removed, vec!["banana"]);
+        let changed_codes: Vec<(&str, Option<&str>, Option<&str>)> = diff
+            .changed_codes
+            .iter()
+            .map(|change| {
+                (
+                    change.code.as_str(),
+                    change.old_word.as_deref(),
+                    change.new_word.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changed_codes,
+            vec![
+                ("12", Some("banana"), Some("cherry")),
+                ("13", Some("cherry"), Some("date")),
+                ("14", None, Some("elder")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+mod format_tests {
+    use tidy::formats::*;
+    use tidy::metadata::{TabularFormat, TabularOptions};
+
+    fn make_lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn write_table(table: &ListTable, format: OutputFormat, line_ending: &str) -> String {
+        let mut output = vec![];
+        write_list_table(&mut output, table, format, line_ending).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn format_lines(table: &ListTable, format: OutputFormat) -> Vec<String> {
+        make_lines(
+            &write_table(table, format, "\n")
+                .lines()
+                .collect::<Vec<&str>>(),
+        )
+    }
+
+    #[test]
+    fn can_make_a_table_with_codes_and_metadata() {
+        let list = make_lines(&["abacus 12", "zoo"]);
+        let codes = Some(("dice", make_lines(&["1", "2"])));
+        let table = make_list_table(&list, codes, None, Some('s'), None, None);
+        assert_eq!(table.columns, vec!["dice", "word", "metadata"]);
+        assert_eq!(
+            table.rows,
+            vec![vec!["1", "abacus", "12"], vec!["2", "zoo", ""]]
+        );
+    }
+
+    #[test]
+    fn names_columns_of_csv_files_by_their_header_row() {
+        let list = make_lines(&["12,abacus,noun", "3,\"ice, cream\""]);
+        let tabular = Some(TabularOptions {
+            format: TabularFormat::Csv,
+            word_column: 1,
+        });
+        let table = make_list_table(&list, None, Some("frequency,word,pos"), None, None, tabular);
+        assert_eq!(table.columns, vec!["word", "frequency", "pos"]);
+        assert_eq!(table.rows[1], vec!["ice, cream", "3", ""]);
+    }
+
+    #[test]
+    fn escapes_quotes_and_backslashes_in_every_format() {
+        let table = ListTable {
+            columns: vec!["word".to_string()],
+            rows: vec![vec![r#"say "hi"\"#.to_string()]],
+        };
+        let expected = [
+            (OutputFormat::Json, r#"  "say \"hi\"\\""#),
+            (OutputFormat::JavaScript, r#"  "say \"hi\"\\""#),
+            (OutputFormat::Rust, r#"    "say \"hi\"\\","#),
+            (OutputFormat::CHeader, r#"    "say \"hi\"\\""#),
+            (OutputFormat::Csv, r#""say ""hi""\""#),
+            (OutputFormat::Markdown, r#"| say "hi"\\ |"#),
+        ];
+        for (format, line) in expected {
+            let lines = format_lines(&table, format);
+            assert!(
+                lines.contains(&line.to_string()),
+                "{} output {:?} doesn't contain {:?}",
+                format,
+                lines,
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn source_code_formats_declare_list_length() {
+        let table = ListTable {
+            columns: vec!["dice".to_string(), "word".to_string()],
+            rows: vec![
+                vec!["1".to_string(), "abacus".to_string()],
+                vec!["2".to_string(), "zoo".to_string()],
+            ],
+        };
+        assert_eq!(
+            format_lines(&table, OutputFormat::Rust),
+            vec![
+                "// (dice, word)",
+                "pub const WORDS: [(&str, &str); 2] = [",
+                "    (\"1\", \"abacus\"),",
+                "    (\"2\", \"zoo\"),",
+                "];"
+            ]
+        );
+        assert!(
+            format_lines(&table, OutputFormat::CHeader)
+                .contains(&"#define WORDS_LENGTH 2".to_string())
+        );
+    }
+
+    #[test]
+    fn makes_a_c_header_of_an_empty_list_that_compiles() {
+        let table = ListTable {
+            columns: vec!["word".to_string()],
+            rows: vec![],
+        };
+        let lines = format_lines(&table, OutputFormat::CHeader);
+        assert!(lines.contains(&"#define WORDS_LENGTH 0".to_string()));
+        assert!(lines.contains(&"static const char *const words[1] = {".to_string()));
+        assert!(lines.contains(&"    0".to_string()));
+    }
+
+    #[test]
+    fn writes_csv_fields_with_line_breaks_whole() {
+        let table = ListTable {
+            columns: vec!["word".to_string(), "note".to_string()],
+            rows: vec![
+                vec!["abacus".to_string(), "two\nlines".to_string()],
+                vec!["zoo".to_string(), String::new()],
+            ],
+        };
+        assert_eq!(
+            write_table(&table, OutputFormat::Csv, "\r\n"),
+            "word,note\r\nabacus,\"two\nlines\"\r\nzoo,\r\n"
+        );
+    }
+
+    use std::io::{self, Write};
+    use tidy::file_writer::{LineEnding, PrintRequest, write_list};
+
+    #[test]
+    fn can_write_list_with_a_template_and_crlf_line_endings() {
+        let print_req = PrintRequest {
+            tidied_list: make_lines(&["abacus 12", "zoo 4"]),
+            dice_sides: Some(vec![6]),
+            ignore_after_delimiter: Some('s'),
+            template: Some(parse_line_template("{index}:{dice} {word} ({metadata})").unwrap()),
+            line_ending: LineEnding::Crlf,
+            ..Default::default()
+        };
+        let mut output = vec![];
+        write_list(&mut output, &print_req).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "1:1 abacus (12)\r\n2:2 zoo (4)\r\n"
+        );
+    }
+
+    #[test]
+    fn writes_header_row_then_each_line_with_its_code() {
+        let print_req = PrintRequest {
+            tidied_list: make_lines(&["abacus", "zoo"]),
+            dice_sides: Some(vec![6]),
+            header_row: Some("word".to_string()),
+            ..Default::default()
+        };
+        let mut output = vec![];
+        write_list(&mut output, &print_req).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "word\n1\tabacus\n2\tzoo\n"
+        );
+    }
+
+    /// A writer that always fails, like a full disk
+    struct FullDisk;
+    impl Write for FullDisk {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk is full"))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::other("disk is full"))
+        }
+    }
+
+    #[test]
+    fn returns_errors_from_the_writer() {
+        let print_req = PrintRequest {
+            tidied_list: make_lines(&["abacus", "zoo"]),
+            ..Default::default()
+        };
+        assert!(write_list(FullDisk, &print_req).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+mod generate_tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use tidy::generate::*;
+
+    fn make_lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn removes_dice_rolls_and_duplicates_from_generator_list() {
+        let lines = make_lines(&["11\tpolish", "12\tPolish", "", "13\tzebra"]);
+        assert_eq!(
+            make_generator_list(&lines, Capitalization::AsIs).unwrap(),
+            vec!["polish", "Polish", "zebra"]
+        );
+        // Once capitalized, "polish" and "Polish" are the same word
+        assert_eq!(
+            make_generator_list(&lines, Capitalization::FirstLetter).unwrap(),
+            vec!["Polish", "Zebra"]
+        );
+        assert!(make_generator_list(&make_lines(&["", " "]), Capitalization::AsIs).is_err());
+    }
+
+    #[test]
+    fn can_generate_passphrases_with_separators_and_digits() {
+        let list = make_lines(&["apple", "banana", "cherry"]);
+        let options = PassphraseOptions {
+            number_of_words: 4,
+            separator: "-".to_string(),
+            digits: 3,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+        for _n in 0..20 {
+            let passphrase = generate_passphrase(&list, &options, &mut rng);
+            let parts: Vec<&str> = passphrase.split('-').collect();
+            assert_eq!(parts.len(), 5);
+            assert!(
+                parts[..4]
+                    .iter()
+                    .all(|word| list.contains(&word.to_string()))
+            );
+            assert_eq!(parts[4].len(), 3);
+            assert!(parts[4].chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn can_calculate_entropy_of_passphrases() {
+        let options = PassphraseOptions::default();
+        assert_eq!(
+            calc_passphrase_entropy(7_776, &options),
+            6.0 * 12.92481250360578
+        );
+        assert_eq!(calc_passphrase_entropy(1, &options), 0.0);
+    }
+}
@@ -2,7 +2,7 @@ mod ignore_tests {
     use tidy::*;
 
     fn make_list() -> Vec<String> {
-        vec!["mA1,word1 mB1", "mA2,word2 mB2", "mA3,word3 mB3", "A,B,C"]
+        ["mA1,word1 mB1", "mA2,word2 mB2", "mA3,word3 mB3", "A,B,C"]
             .iter()
             .map(|x| x.to_string())
             .collect()
@@ -16,7 +16,7 @@ mod ignore_tests {
             maximum_length: Some(10),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"mA1,word1 mB1".to_string()));
     }
     #[test]
@@ -27,8 +27,357 @@ mod ignore_tests {
             maximum_length: Some(10),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         println!("{:?}", new_list);
         assert!(new_list.contains(&"mA1,word1 mB1".to_string()));
     }
+    #[test]
+    fn can_ignore_metadata_on_both_sides_of_a_word() {
+        let this_tidy_request = TidyRequest {
+            list: [
+                "11111\tabacus\t0.0012",
+                "11112\tabbey\t0.0034",
+                "11113\tab\t0.1",
+            ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect(),
+            ignore_before_delimiter: Some('t'),
+            ignore_after_delimiter: Some('t'),
+            should_remove_prefix_words: true,
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(
+            new_list,
+            vec!["11111\tabacus\t0.0012", "11112\tabbey\t0.0034"]
+        );
+    }
+
+    use tidy::display_information::make_list_free_of_metadata;
+    #[test]
+    fn can_remove_metadata_from_both_sides_for_attributes() {
+        let list = vec![
+            "11111\tabacus\t0.0012".to_string(),
+            "11112\tabbey".to_string(),
+        ];
+        assert_eq!(
+            make_list_free_of_metadata(&list, Some('t'), Some('t'), None),
+            vec!["abacus", "abbey"]
+        );
+    }
+
+    use tidy::metadata::{TabularFormat, TabularOptions};
+    #[test]
+    fn can_ignore_other_columns_of_a_csv_file() {
+        let this_tidy_request = TidyRequest {
+            list: ["1,zebra,\"striped, horse\"", "2,ox,x", "3,apple,fruit"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            tabular: Some(TabularOptions {
+                format: TabularFormat::Csv,
+                word_column: 1,
+            }),
+            minimum_length: Some(3),
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(
+            new_list,
+            vec![
+                "1,zebra,\"striped, horse\"".to_string(),
+                "3,apple,fruit".to_string()
+            ]
+        );
+    }
+
+    use tidy::metadata::group_into_records;
+    #[test]
+    fn keeps_quoted_line_breaks_in_one_csv_record() {
+        let lines: Vec<String> = ["1,zebra,\"striped", "horse\"", "2,apple,fruit"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        let records = group_into_records(lines, TabularFormat::Csv).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                "1,zebra,\"striped\nhorse\"".to_string(),
+                "2,apple,fruit".to_string()
+            ]
+        );
+        let this_tidy_request = TidyRequest {
+            list: records,
+            tabular: Some(TabularOptions {
+                format: TabularFormat::Csv,
+                word_column: 1,
+            }),
+            minimum_length: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            tidy_list(this_tidy_request).unwrap(),
+            vec![
+                "1,zebra,\"striped\nhorse\"".to_string(),
+                "2,apple,fruit".to_string()
+            ]
+        );
+    }
+
+    use std::path::PathBuf;
+    use tidy::metadata::remove_header_rows;
+    #[test]
+    fn header_rows_of_inputted_files_must_match() {
+        let filenames = vec![PathBuf::from("a.csv"), PathBuf::from("b.csv")];
+        let mut word_lists_by_file = vec![
+            vec!["rank,word".to_string(), "1,apple".to_string()],
+            vec!["rank,word".to_string(), "2,zebra".to_string()],
+        ];
+        assert_eq!(
+            remove_header_rows(&mut word_lists_by_file, &filenames, TabularFormat::Csv).unwrap(),
+            Some("rank,word".to_string())
+        );
+        assert_eq!(word_lists_by_file[1], vec!["2,zebra".to_string()]);
+
+        let mut mismatched_word_lists_by_file = vec![
+            vec!["rank,word".to_string(), "1,apple".to_string()],
+            vec!["word,rank".to_string(), "zebra,2".to_string()],
+        ];
+        assert!(
+            remove_header_rows(
+                &mut mismatched_word_lists_by_file,
+                &filenames,
+                TabularFormat::Csv
+            )
+            .is_err()
+        );
+    }
+
+    use tidy::metadata::{ColumnSelector, resolve_column};
+    #[test]
+    fn can_find_word_column_by_name() {
+        let header_rows = vec!["rank,word,note".to_string(), "rank,word,x".to_string()];
+        assert_eq!(
+            resolve_column(
+                "--column",
+                &ColumnSelector::Name("word".to_string()),
+                &header_rows,
+                TabularFormat::Csv
+            )
+            .unwrap(),
+            1
+        );
+        let mismatched_header_rows = vec!["rank,word".to_string(), "word,rank".to_string()];
+        assert!(
+            resolve_column(
+                "--column",
+                &ColumnSelector::Name("word".to_string()),
+                &mismatched_header_rows,
+                TabularFormat::Csv
+            )
+            .is_err()
+        );
+    }
+
+    use tidy::metadata::ScoreOptions;
+    fn make_scored_list() -> Vec<String> {
+        [
+            "paper\t100",
+            "news\t500",
+            "newspaper\t900",
+            "son\t50",
+            "sun\t80",
+            "radius\t200",
+            "radical\t300",
+        ]
+        .iter()
+        .map(|x| x.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn takes_words_with_the_best_scores_first() {
+        let this_tidy_request = TidyRequest {
+            list: make_scored_list(),
+            ignore_after_delimiter: Some('t'),
+            score: Some(ScoreOptions {
+                column: 1,
+                lower_is_better: false,
+            }),
+            take_first: Some(2),
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["newspaper\t900", "news\t500"]);
+    }
+
+    #[test]
+    fn keeps_words_with_better_scores_when_choosing_between_words() {
+        let this_tidy_request = TidyRequest {
+            list: make_scored_list(),
+            ignore_after_delimiter: Some('t'),
+            score: Some(ScoreOptions {
+                column: 1,
+                lower_is_better: false,
+            }),
+            should_remove_prefix_words: true,
+            maximum_shared_prefix_length: Some(4),
+            homophones_list: Some(vec![vec!["son".to_string(), "sun".to_string()]]),
+            sort_by_score: true,
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(
+            new_list,
+            vec!["newspaper\t900", "radical\t300", "paper\t100", "sun\t80"]
+        );
+    }
+
+    #[test]
+    fn can_treat_scores_as_ranks() {
+        let this_tidy_request = TidyRequest {
+            list: ["zebra,3", "apple,1", "ox,2"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            tabular: Some(TabularOptions {
+                format: TabularFormat::Csv,
+                word_column: 0,
+            }),
+            score: Some(ScoreOptions {
+                column: 1,
+                lower_is_better: true,
+            }),
+            sort_by_score: true,
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["apple,1", "ox,2", "zebra,3"]);
+    }
+
+    use tidy::metadata::MergePolicy;
+    fn make_list_with_duplicate_words() -> Vec<String> {
+        ["Apple\t12", "zebra\t5", "apple\t30", "APPLE"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn can_lowercase_words_while_ignoring_metadata() {
+        // Without a merge policy, lines with different metadata are all kept
+        let this_tidy_request = TidyRequest {
+            list: make_list_with_duplicate_words(),
+            ignore_after_delimiter: Some('t'),
+            to_lowercase: true,
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(
+            new_list,
+            vec!["apple\t12", "zebra\t5", "apple\t30", "apple"]
+        );
+
+        let this_tidy_request = TidyRequest {
+            list: make_list_with_duplicate_words(),
+            ignore_after_delimiter: Some('t'),
+            to_lowercase: true,
+            merge: Some(MergePolicy::KeepFirst),
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["apple\t12", "zebra\t5"]);
+    }
+
+    #[test]
+    fn only_removes_whole_duplicate_lines_by_default() {
+        let this_tidy_request = TidyRequest {
+            list: ["apple\t1", "apple\t2", "apple\t1"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            ignore_after_delimiter: Some('t'),
+            ..Default::default()
+        };
+        let (new_list, audit_records) = tidy_list_with_audit(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["apple\t1", "apple\t2"]);
+        let duplicate = audit_records
+            .iter()
+            .find(|record| record.removed_by.as_deref() == Some("duplicate"))
+            .unwrap();
+        assert_eq!(duplicate.reason.as_deref(), Some("same line as line 1"));
+    }
+
+    #[test]
+    fn keeps_lines_with_different_metadata_through_whole_list_stages() {
+        let this_tidy_request = TidyRequest {
+            list: ["apple\t1", "app\t5", "apple\t2", "apple\t1"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            ignore_after_delimiter: Some('t'),
+            should_remove_prefix_words: true,
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["apple\t1", "apple\t2"]);
+    }
+
+    #[test]
+    fn can_keep_the_duplicate_word_with_the_highest_score() {
+        let this_tidy_request = TidyRequest {
+            list: make_list_with_duplicate_words(),
+            ignore_after_delimiter: Some('t'),
+            to_lowercase: true,
+            score: Some(ScoreOptions {
+                column: 1,
+                lower_is_better: false,
+            }),
+            merge: Some(MergePolicy::KeepHighestScore),
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["apple\t30", "zebra\t5"]);
+    }
+
+    #[test]
+    fn keeping_the_highest_score_needs_a_score() {
+        let this_tidy_request = TidyRequest {
+            list: make_list_with_duplicate_words(),
+            ignore_after_delimiter: Some('t'),
+            merge: Some(MergePolicy::KeepHighestScore),
+            ..Default::default()
+        };
+        assert!(tidy_list(this_tidy_request).is_err());
+    }
+
+    #[test]
+    fn can_concatenate_metadata_of_duplicate_words() {
+        let this_tidy_request = TidyRequest {
+            list: make_list_with_duplicate_words(),
+            ignore_after_delimiter: Some('t'),
+            to_lowercase: true,
+            merge: Some(MergePolicy::Concatenate),
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["apple\t12;30", "zebra\t5"]);
+
+        let this_tidy_request = TidyRequest {
+            list: ["1,Apple,noun", "2,apple,fruit", "3,apple,noun"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            tabular: Some(TabularOptions {
+                format: TabularFormat::Csv,
+                word_column: 1,
+            }),
+            to_lowercase: true,
+            merge: Some(MergePolicy::Concatenate),
+            ..Default::default()
+        };
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["1;2;3,apple,noun;fruit"]);
+    }
 }
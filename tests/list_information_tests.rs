@@ -11,7 +11,7 @@ mod list_information_tests {
 
     #[test]
     fn can_calculate_assumed_entropy_per_character_of_generated_list() {
-        let list: Vec<String> = vec!["to", "canopy", "cold", "seasons", "fire", "Christmas"]
+        let list: Vec<String> = ["to", "canopy", "cold", "seasons", "fire", "Christmas"]
             .iter()
             .map(|x| x.to_string())
             .collect();
@@ -20,7 +20,7 @@ mod list_information_tests {
 
     #[test]
     fn can_calculate_mean_edit_distance() {
-        let list: Vec<String> = vec![
+        let list: Vec<String> = [
             "bat", "cat", "rat", "hat", "mat", "tat", "fat", "oat", "pat", "sat", "vat",
         ]
         .iter()
@@ -28,17 +28,17 @@ mod list_information_tests {
         .collect();
         assert_eq!(find_mean_edit_distance(&list), 1.0);
 
-        let list2: Vec<String> = vec!["abcd", "abce", "abxz"]
+        let list2: Vec<String> = ["abcd", "abce", "abxz"]
             .iter()
             .map(|x| x.to_string())
             .collect();
         assert_eq!(find_mean_edit_distance(&list2), 1.6666666666666667);
 
-        let list3: Vec<String> = vec!["abcd", "abce", "abxz", "abpt"]
+        let list3: Vec<String> = ["abcd", "abce", "abxz", "abpt"]
             .iter()
             .map(|x| x.to_string())
             .collect();
-        assert_eq!(find_mean_edit_distance(&list3), (11.0 / 6.0) as f64);
+        assert_eq!(find_mean_edit_distance(&list3), 11.0 / 6.0);
     }
 
     #[test]
@@ -71,7 +71,7 @@ mod list_information_tests {
 
     #[test]
     fn can_find_longest_shared_prefix_in_a_list() {
-        let list: Vec<String> = vec![
+        let list: Vec<String> = [
             "to",
             "canopy",
             "cold",
@@ -86,7 +86,7 @@ mod list_information_tests {
         .collect();
         assert_eq!(find_longest_shared_prefix(&list, None), 7);
 
-        let list: Vec<String> = vec!["to", "canopy", "cancel", "seasons", "fire", "Christmas"]
+        let list: Vec<String> = ["to", "canopy", "cancel", "seasons", "fire", "Christmas"]
             .iter()
             .map(|x| x.to_string())
             .collect();
@@ -94,7 +94,7 @@ mod list_information_tests {
     }
     #[test]
     fn can_get_shortest_word_length() {
-        let list: Vec<String> = vec!["canopy", "to", "cold", "seasons", "fire", "Christmas"]
+        let list: Vec<String> = ["canopy", "to", "cold", "seasons", "fire", "Christmas"]
             .iter()
             .map(|x| x.to_string())
             .collect();
@@ -102,10 +102,23 @@ mod list_information_tests {
     }
     #[test]
     fn can_get_mean_word_length() {
-        let list: Vec<String> = vec!["canopy", "to", "cold", "seasons", "fire", "Christmas"]
+        let list: Vec<String> = ["canopy", "to", "cold", "seasons", "fire", "Christmas"]
             .iter()
             .map(|x| x.to_string())
             .collect();
         assert_eq!(mean_word_length(&list), 5.3333335);
     }
+    #[test]
+    fn same_seed_generates_the_same_samples() {
+        let list: Vec<String> = (1..=1000).map(|n| n.to_string()).collect();
+        let samples = generate_samples(&list, Some(42)).unwrap();
+        assert_eq!(samples.len(), 30);
+        assert_eq!(generate_samples(&list, Some(42)).unwrap(), samples);
+        assert_ne!(generate_samples(&list, Some(43)).unwrap(), samples);
+    }
+    #[test]
+    fn reports_an_error_when_attributes_of_an_empty_list_are_requested() {
+        assert!(generate_samples(&[], None).is_err());
+        assert!(display_list_information(&[], 1, false, None, None, None, false, None).is_err());
+    }
 }
@@ -1,5 +1,6 @@
 mod list_manipulation_tests {
     use tidy::dice::print_as_dice; // not exactly sure why I need this here...
+    use tidy::error::TidyError;
     use tidy::list_manipulations::reverse_all_words;
     use tidy::*;
 
@@ -74,7 +75,7 @@ mod list_manipulation_tests {
             .iter()
             .map(|x| x.to_string())
             .collect(),
-            vec![
+            [
                 "énigme", "enlever", "abbey", "zoo", "Zambia", "eager", "ezra", "año", "antena",
                 "anaconda", "aptitude",
             ]
@@ -90,7 +91,7 @@ mod list_manipulation_tests {
             list: make_lists().0,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"tea".to_string()));
         assert!(new_list.contains(&"apple".to_string()));
         assert!(new_list.len() == make_lists().0.len() - 2);
@@ -102,14 +103,14 @@ mod list_manipulation_tests {
     use tidy::list_manipulations::dedup_without_sorting;
     #[test]
     fn can_remove_duplicate_words_when_combining_two_lists() {
-        let mut list: Vec<String> = vec!["zoo", "anaconda", "zoo", "aptitude"]
+        let mut list: Vec<String> = ["zoo", "anaconda", "zoo", "aptitude"]
             .iter()
             .map(|word| word.to_string())
             .collect();
         let new_list = dedup_without_sorting(&mut list);
         assert!(new_list.contains(&"zoo".to_string()));
         assert!(new_list.len() == 3);
-        assert!(new_list[new_list.len() - 1] == "aptitude".to_string());
+        assert!(new_list[new_list.len() - 1] == "aptitude");
     }
 
     #[test]
@@ -120,10 +121,10 @@ mod list_manipulation_tests {
             locale: "en-US".to_string(),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
-        assert!(new_list[0] == "actor".to_string());
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert!(new_list[0] == "actor");
         assert!(new_list.contains(&"station".to_string()));
-        assert!(new_list[new_list.len() - 1] == "zookeeper".to_string());
+        assert!(new_list[new_list.len() - 1] == "zookeeper");
     }
 
     #[test]
@@ -133,8 +134,8 @@ mod list_manipulation_tests {
             sort_alphabetically: false,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
-        assert!(new_list[0] == "zookeeper".to_string());
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert!(new_list[0] == "zookeeper");
         assert!(new_list.contains(&"apple".to_string()));
         assert_eq!(new_list[new_list.len() - 4], "apple".to_string());
     }
@@ -147,9 +148,9 @@ mod list_manipulation_tests {
             locale: "en-US".to_string(),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
-        assert!(new_list[0] == "stationary".to_string());
-        assert!(new_list[1] == "addiction".to_string());
+        let new_list = tidy_list(this_tidy_request).unwrap();
+        assert!(new_list[0] == "stationary");
+        assert!(new_list[1] == "addiction");
     }
 
     #[test]
@@ -158,7 +159,7 @@ mod list_manipulation_tests {
             list: make_lists().1,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.len() == make_lists().1.len() - 2);
     }
 
@@ -169,7 +170,7 @@ mod list_manipulation_tests {
             take_first: Some(4),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         println!("List length now {}: {:?}", new_list.len(), new_list);
         assert_eq!(new_list.len(), 4);
         assert_ne!(new_list.len(), 3);
@@ -182,7 +183,7 @@ mod list_manipulation_tests {
             list: make_lists().1,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"wizard".to_string()));
     }
 
@@ -192,7 +193,7 @@ mod list_manipulation_tests {
             list: make_lists().1,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"h as spaces".to_string()));
     }
 
@@ -203,7 +204,7 @@ mod list_manipulation_tests {
             should_straighten_quotes: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"\"smart\"".to_string()));
         assert!(new_list.contains(&"'quotes'".to_string()));
     }
@@ -214,7 +215,7 @@ mod list_manipulation_tests {
             should_delete_integers: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"active".to_string()));
     }
 
@@ -225,7 +226,7 @@ mod list_manipulation_tests {
             should_delete_nonalphanumeric: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"1968clad".to_string()));
         assert!(new_list.contains(&"take".to_string()));
     }
@@ -237,7 +238,7 @@ mod list_manipulation_tests {
             should_remove_nonalphanumeric: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
 
         assert!(new_list.contains(&"Uppercase".to_string()));
         assert!(new_list.contains(&"京".to_string()));
@@ -254,7 +255,7 @@ mod list_manipulation_tests {
             should_remove_nonalphabetic: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
 
         assert!(new_list.contains(&"Uppercase".to_string()));
         assert!(new_list.contains(&"京".to_string()));
@@ -270,7 +271,7 @@ mod list_manipulation_tests {
             should_remove_non_latin_alphabetic: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
 
         assert!(new_list.contains(&"Uppercase".to_string()));
         assert!(!new_list.contains(&"京".to_string()));
@@ -287,7 +288,7 @@ mod list_manipulation_tests {
             should_remove_nonascii: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
 
         assert!(new_list.contains(&"Uppercase".to_string()));
         assert!(new_list.contains(&"hello109823".to_string()));
@@ -304,7 +305,7 @@ mod list_manipulation_tests {
             should_delete_before_first_delimiter: Some('\t'),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"active".to_string()));
         assert!(new_list.contains(&"acclaim".to_string()));
         // Only remove through FIRST tab
@@ -318,7 +319,7 @@ mod list_manipulation_tests {
             should_delete_before_first_delimiter: Some(' '),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"clad".to_string()));
         // Check that it only removes characters through first space, rather than just
         // between first space and second space, for example
@@ -334,7 +335,7 @@ mod list_manipulation_tests {
             should_delete_before_first_delimiter: Some(','),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"203478".to_string()));
         assert!(new_list.contains(&"h as spaces".to_string()));
     }
@@ -346,7 +347,7 @@ mod list_manipulation_tests {
             should_delete_after_first_delimiter: Some('\t'),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"11225".to_string()));
         assert!(new_list.contains(&"11152".to_string()));
         // remove after FIRST tab
@@ -359,7 +360,7 @@ mod list_manipulation_tests {
             should_delete_after_first_delimiter: Some(' '),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"19-6-8".to_string()));
         assert!(new_list.contains(&"13910".to_string()));
         assert!(new_list.contains(&"post-modern".to_string()));
@@ -372,7 +373,7 @@ mod list_manipulation_tests {
             should_delete_after_first_delimiter: Some(','),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"comma".to_string()));
         assert!(new_list.contains(&"h as spaces".to_string()));
     }
@@ -384,14 +385,14 @@ mod list_manipulation_tests {
             to_lowercase: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"charlie".to_string()));
         let this_tidy_request = TidyRequest {
             list: make_lists().1,
             to_lowercase: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"vacation".to_string()));
         assert!(new_list.contains(&"ardor".to_string()));
     }
@@ -403,7 +404,7 @@ mod list_manipulation_tests {
             should_remove_prefix_words: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"station".to_string()));
         assert!(new_list.contains(&"stationary".to_string()));
         assert!(!new_list.contains(&"zoo".to_string()));
@@ -418,7 +419,7 @@ mod list_manipulation_tests {
             should_remove_prefix_words: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"séc".to_string()));
         assert!(new_list.contains(&"sécréter".to_string()));
     }
@@ -430,7 +431,7 @@ mod list_manipulation_tests {
             should_remove_suffix_words: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"keeper".to_string()));
         assert!(new_list.contains(&"apple".to_string()));
     }
@@ -442,7 +443,7 @@ mod list_manipulation_tests {
             should_remove_nonalphanumeric: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"19-6-8 clad".to_string()));
         assert!(new_list.contains(&"world999".to_string()));
         assert!(new_list.contains(&"take".to_string()));
@@ -455,7 +456,7 @@ mod list_manipulation_tests {
             should_remove_nonalphabetic: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"19-6-8 clad".to_string()));
         assert!(!new_list.contains(&"world999".to_string()));
         assert!(!new_list.contains(&"world".to_string()));
@@ -473,7 +474,7 @@ mod list_manipulation_tests {
             should_remove_integers: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"19-6-8 clad".to_string()));
         assert!(!new_list.contains(&"world999".to_string()));
         assert!(new_list.contains(&"be".to_string()));
@@ -487,7 +488,7 @@ mod list_manipulation_tests {
             minimum_length: Some(3),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"I".to_string()));
         assert!(!new_list.contains(&"be".to_string()));
         assert!(new_list.contains(&"tea".to_string()));
@@ -502,7 +503,7 @@ mod list_manipulation_tests {
             maximum_length: Some(7),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"addiction".to_string()));
         assert!(!new_list.contains(&"zookeeper".to_string()));
         assert!(!new_list.contains(&"stationary".to_string()));
@@ -520,7 +521,7 @@ mod list_manipulation_tests {
             maximum_length: Some(7),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"active".to_string()));
         assert!(new_list.contains(&"acclaim".to_string()));
         assert!(!new_list.contains(&"word with spacaes in it".to_string()));
@@ -533,7 +534,7 @@ mod list_manipulation_tests {
             maximum_shared_prefix_length: Some(3),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"zoo".to_string()));
         assert!(!new_list.contains(&"zookeeper".to_string()));
         assert!(new_list.contains(&"station".to_string()));
@@ -542,38 +543,198 @@ mod list_manipulation_tests {
         assert!(!new_list.contains(&"apple".to_string()));
     }
 
+    use tidy::list_manipulations::{MatchList, MatchOptions};
     #[test]
     fn can_remove_reject_words() {
-        let words_to_reject: Vec<String> = vec!["mistake", "carnival"]
+        let words_to_reject: Vec<String> = ["mistake", "carnival"]
             .iter()
             .map(|x| x.to_string())
             .collect();
 
         let this_tidy_request = TidyRequest {
             list: make_lists().1,
-            reject_list: Some(words_to_reject),
+            reject_list: Some(MatchList::new(&words_to_reject, MatchOptions::default())),
             to_lowercase: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(!new_list.contains(&"mistake".to_string()));
         assert!(!new_list.contains(&"carnival".to_string()));
         assert!(new_list.contains(&"wizard".to_string()));
     }
 
+    #[test]
+    fn can_loosely_match_reject_words() {
+        let words_to_reject: Vec<String> =
+            ["Café", "Mistake"].iter().map(|x| x.to_string()).collect();
+        let list: Vec<String> = ["cafe", "CAFÉ", "cafe\u{301}", "mistake", "wizard"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        let this_tidy_request = TidyRequest {
+            list: list.clone(),
+            reject_list: Some(MatchList::new(
+                &words_to_reject,
+                MatchOptions {
+                    case_insensitive: true,
+                    normalized: false,
+                    diacritic_insensitive: true,
+                },
+            )),
+            ..Default::default()
+        };
+        assert_eq!(tidy_list(this_tidy_request).unwrap(), vec!["wizard"]);
+
+        // Only normalizing means "café" with a combining accent is rejected,
+        // but "CAFÉ" is not
+        let this_tidy_request = TidyRequest {
+            list: ["café", "cafe\u{301}", "CAFÉ"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            reject_list: Some(MatchList::new(
+                &["café"],
+                MatchOptions {
+                    normalized: true,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        };
+        assert_eq!(tidy_list(this_tidy_request).unwrap(), vec!["CAFÉ"]);
+    }
+
+    #[test]
+    fn can_run_stages_in_a_given_order() {
+        let make_request = |stage_order: Option<Vec<String>>| TidyRequest {
+            list: vec!["News".to_string(), "newspaper".to_string()],
+            to_lowercase: true,
+            should_remove_prefix_words: true,
+            stage_order,
+            ..Default::default()
+        };
+        // By default, words are lowercased before prefix words are removed
+        assert_eq!(tidy_list(make_request(None)).unwrap(), vec!["newspaper"]);
+        // But "News" isn't a prefix of "newspaper" until it's lowercased
+        let stage_order = Some(vec!["remove-prefix".to_string(), "lowercase".to_string()]);
+        assert_eq!(
+            tidy_list(make_request(stage_order)).unwrap(),
+            vec!["news", "newspaper"]
+        );
+
+        // Stages have to exist and be used
+        for stage_name in ["remove-vowels", "schlinkert-prune"] {
+            let stage_order = Some(vec![stage_name.to_string()]);
+            assert!(matches!(
+                tidy_list(make_request(stage_order)),
+                Err(TidyError::InvalidOption { ref option, .. }) if option == "--order"
+            ));
+        }
+    }
+
+    #[test]
+    fn same_seed_takes_and_prints_the_same_random_words() {
+        let make_request = |seed: Option<u64>| TidyRequest {
+            list: (1..=100).map(|n| format!("word{}", n)).collect(),
+            take_rand: Some(50),
+            print_rand: Some(10),
+            seed,
+            ..Default::default()
+        };
+        let first_run = tidy_list(make_request(Some(7776))).unwrap();
+        assert_eq!(first_run.len(), 10);
+        assert_eq!(tidy_list(make_request(Some(7776))).unwrap(), first_run);
+        assert_ne!(tidy_list(make_request(Some(6))).unwrap(), first_run);
+    }
+
+    #[test]
+    fn can_explain_why_each_word_was_removed() {
+        let this_tidy_request = TidyRequest {
+            list: ["news", "newspaper", "", "Their", "there", "x", "cat", "bat"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            to_lowercase: true,
+            minimum_length: Some(2),
+            homophones_list: Some(vec![vec!["there".to_string(), "their".to_string()]]),
+            minimum_edit_distance: Some(2),
+            should_remove_prefix_words: true,
+            ..Default::default()
+        };
+        let (new_list, audit_records) = tidy_list_with_audit(this_tidy_request).unwrap();
+        assert_eq!(new_list, vec!["newspaper", "there"]);
+
+        let removals: Vec<(&str, Option<&str>, Option<&str>)> = audit_records
+            .iter()
+            .map(|record| {
+                (
+                    record.word.as_str(),
+                    record.removed_by.as_deref(),
+                    record.reason.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            removals,
+            vec![
+                ("news", Some("remove-prefix"), Some("prefix of newspaper")),
+                ("newspaper", None, None),
+                ("", Some("blank"), None),
+                ("their", Some("homophones"), Some("homophone of there")),
+                ("there", None, None),
+                ("x", Some("minimum-word-length"), None),
+                (
+                    "cat",
+                    Some("minimum-edit-distance"),
+                    Some("edit distance 1 from bat")
+                ),
+                (
+                    "bat",
+                    Some("minimum-edit-distance"),
+                    Some("edit distance 1 from cat")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_locale_and_normalization_form() {
+        let this_tidy_request = TidyRequest {
+            list: vec!["word".to_string()],
+            sort_alphabetically: true,
+            locale: "not a locale!".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            tidy_list(this_tidy_request),
+            Err(TidyError::InvalidOption { ref option, .. }) if option == "--locale"
+        ));
+
+        let this_tidy_request = TidyRequest {
+            list: vec!["word".to_string()],
+            normalization_form: Some("nfx".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            tidy_list(this_tidy_request),
+            Err(TidyError::InvalidOption { ref option, .. }) if option == "--normalization-form"
+        ));
+    }
+
     #[test]
     fn can_remove_all_words_not_on_approved_list_words() {
-        let approved_words: Vec<String> = vec!["take", "vAcation", "airplane"]
+        let approved_words: Vec<String> = ["take", "vAcation", "airplane"]
             .iter()
             .map(|x| x.to_string())
             .collect();
 
         let this_tidy_request = TidyRequest {
             list: make_lists().1,
-            approved_list: Some(approved_words),
+            approved_list: Some(MatchList::new(&approved_words, MatchOptions::default())),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"take".to_string()));
         assert!(new_list.contains(&"vAcation".to_string()));
         assert!(!new_list.contains(&"carnival".to_string()));
@@ -583,16 +744,16 @@ mod list_manipulation_tests {
 
     #[test]
     fn can_remove_specified_homophones() {
-        let homophone1 = ("be".to_string(), "bee".to_string());
-        let homophone2 = ("right".to_string(), "write".to_string());
-        let homophone3 = ("tea".to_string(), "tee".to_string());
+        let homophone1 = vec!["be".to_string(), "bee".to_string()];
+        let homophone2 = vec!["right".to_string(), "write".to_string()];
+        let homophone3 = vec!["tea".to_string(), "tee".to_string()];
         let this_tidy_request = TidyRequest {
             list: make_lists().1,
             homophones_list: Some(vec![homophone1, homophone2, homophone3]),
             to_lowercase: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert!(new_list.contains(&"tea".to_string()));
         assert!(!new_list.contains(&"tee".to_string()));
         assert!(new_list.contains(&"be".to_string()));
@@ -600,6 +761,24 @@ mod list_manipulation_tests {
         assert!(new_list.contains(&"mistake".to_string()));
     }
 
+    #[test]
+    fn can_remove_groups_of_homophones() {
+        let list: Vec<String> = ["two", "sun", "too", "son", "to"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        let homophones = vec![
+            vec!["to".to_string(), "too".to_string(), "two".to_string()],
+            vec!["sun".to_string(), "son".to_string()],
+        ];
+        let this_tidy_request = TidyRequest {
+            list,
+            homophones_list: Some(homophones),
+            ..Default::default()
+        };
+        assert_eq!(tidy_list(this_tidy_request).unwrap(), vec!["sun", "to"]);
+    }
+
     #[test]
     fn can_sort_accented_and_capitalized_letters_properly() {
         let this_tidy_request = TidyRequest {
@@ -609,9 +788,9 @@ mod list_manipulation_tests {
             normalization_form: Some("nfkd".to_string()),
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
 
-        let how_list_should_be_sorted: Vec<String> = vec![
+        let how_list_should_be_sorted: Vec<String> = [
             "abbey",
             "anaconda",
             "antena",
@@ -719,19 +898,119 @@ mod list_manipulation_tests {
 
     #[test]
     fn can_print_dice_rolls_of_base_6() {
-        assert_eq!(print_as_dice(0, 6, 7776, false), "11111".to_string());
-        assert_eq!(print_as_dice(7775, 6, 7776, false), "66666".to_string());
-        assert_eq!(print_as_dice(2548, 6, 7776, false), "26555".to_string());
-        assert_eq!(print_as_dice(2548, 6, 7000, false), "26555".to_string());
+        assert_eq!(
+            print_as_dice(0, 6, 7776, false).unwrap(),
+            "11111".to_string()
+        );
+        assert_eq!(
+            print_as_dice(7775, 6, 7776, false).unwrap(),
+            "66666".to_string()
+        );
+        assert_eq!(
+            print_as_dice(2548, 6, 7776, false).unwrap(),
+            "26555".to_string()
+        );
+        assert_eq!(
+            print_as_dice(2548, 6, 7000, false).unwrap(),
+            "26555".to_string()
+        );
     }
     #[test]
     fn can_print_dice_rolls_of_base_2() {
-        assert_eq!(print_as_dice(1, 2, 7776, true), "0000000000001".to_string());
-        assert_eq!(print_as_dice(127, 2, 128, true), "1111111".to_string());
+        assert_eq!(
+            print_as_dice(1, 2, 7776, true).unwrap(),
+            "0000000000001".to_string()
+        );
+        assert_eq!(
+            print_as_dice(127, 2, 128, true).unwrap(),
+            "1111111".to_string()
+        );
     }
     #[test]
     fn can_print_dice_rolls_of_base_20() {
-        assert_eq!(print_as_dice(1000, 20, 8000, false), "03-11-01".to_string());
-        assert_eq!(print_as_dice(1000, 20, 8000, true), "2A0".to_string());
+        assert_eq!(
+            print_as_dice(1000, 20, 8000, false).unwrap(),
+            "03-11-01".to_string()
+        );
+        assert_eq!(
+            print_as_dice(1000, 20, 8000, true).unwrap(),
+            "2A0".to_string()
+        );
+    }
+    #[test]
+    fn reports_dice_with_too_few_or_too_many_sides() {
+        assert!(print_as_dice(0, 1, 7776, false).is_err());
+        assert!(print_as_dice(0, 37, 7776, true).is_err());
+        assert!(print_dice_rolls(0, &[0], 7776, false).is_err());
+    }
+
+    use tidy::dice::{print_as_mixed_dice, print_dice_rolls};
+    #[test]
+    fn can_print_dice_rolls_of_a_set_of_different_dice() {
+        let dice = [20, 12, 10, 8];
+        assert_eq!(print_as_mixed_dice(0, &dice, false), "01-01-01-01");
+        assert_eq!(print_as_mixed_dice(1, &dice, false), "01-01-01-02");
+        assert_eq!(print_as_mixed_dice(8, &dice, false), "01-01-02-01");
+        assert_eq!(print_as_mixed_dice(19_199, &dice, false), "20-12-10-08");
+        assert_eq!(print_as_mixed_dice(7, &[4, 6, 8], false), "118");
+        // One die is rolled as many times as needed, like before
+        assert_eq!(print_dice_rolls(7775, &[6], 7776, false).unwrap(), "66666");
+        assert_eq!(print_dice_rolls(7, &[4, 6, 8], 192, true).unwrap(), "007");
+    }
+
+    use tidy::cards::*;
+    #[test]
+    fn can_print_card_codes_from_black_and_red_cards() {
+        let deck = CardDeck::BlackAndRed;
+        assert_eq!(print_as_cards(0, 26 * 26, deck), "B02-B02");
+        assert_eq!(print_as_cards(675, 26 * 26, deck), "RAc-RAc");
+        assert_eq!(print_as_cards(2703, 2704, deck), "B05-RAc-RAc");
+    }
+
+    #[test]
+    fn can_print_and_decode_card_codes_from_full_decks() {
+        let one_deck = CardDeck::FullDecks(1);
+        assert_eq!(print_as_cards(0, 2704, one_deck), "C02-C02");
+        assert_eq!(print_as_cards(52, 2704, one_deck), "C03-C02");
+        assert_eq!(print_as_cards(2703, 2704, one_deck), "SAc-SAc");
+
+        let two_decks = CardDeck::FullDecks(2);
+        assert_eq!(print_as_cards(103, 104, two_decks), "2SAc");
+        assert_eq!(print_as_cards(104, 104 * 104, two_decks), "1C03-1C02");
+
+        // Every code decodes back to the number it came from
+        for deck in [CardDeck::BlackAndRed, one_deck, two_decks] {
+            for n in 0..3000 {
+                let code = print_as_cards(n, 3000, deck);
+                assert_eq!(decode_cards(&code, deck).unwrap(), n);
+            }
+        }
+        assert!(decode_cards("1C02", one_deck).is_err());
+        assert!(decode_cards("3C02", two_decks).is_err());
+    }
+
+    #[test]
+    fn can_print_and_decode_card_codes_drawn_without_replacement() {
+        let deck = CardDeck::FullDecks(1);
+        // 52 * 51 codes fit in 2 cards, but one more needs a third card
+        assert_eq!(print_as_drawn_cards(2651, 2652, deck), "SAc-SKi");
+        assert_eq!(print_as_drawn_cards(0, 2653, deck), "C02-C03-C04");
+        assert_eq!(
+            count_card_codes_without_replacement(3, deck),
+            Some(52 * 51 * 50)
+        );
+
+        let options = CardOptions {
+            deck: CardDeck::BlackAndRed,
+            without_replacement: true,
+        };
+        for n in 0..(26 * 25 * 24) {
+            let code = print_card_code(n, 26 * 25 * 24, options);
+            let cards: Vec<&str> = code.split('-').collect();
+            assert_eq!(cards.len(), 3);
+            assert!(cards[0] != cards[1] && cards[1] != cards[2] && cards[0] != cards[2]);
+            assert_eq!(decode_card_code(&code, options).unwrap(), n);
+        }
+        assert!(decode_card_code("B02-B02", options).is_err());
     }
 }
@@ -1,6 +1,7 @@
 mod list_reading_tests {
     use crate::list_reading_tests::file_readers::blend_lists;
     use crate::list_reading_tests::file_readers::concatenate_lists;
+    use tidy::error::TidyError;
     use tidy::*;
 
     #[test]
@@ -23,6 +24,26 @@ mod list_reading_tests {
         );
     }
 
+    #[test]
+    fn can_blend_multiple_lists_with_weights() {
+        let word_lists_by_file = vec![
+            vec!["a1", "a2", "a3", "a4", "a5", "a6", "a7", "a8"]
+                .into_iter()
+                .map(|w| w.to_string())
+                .collect(),
+            vec!["b1".to_string(), "b2".to_string(), "b3".to_string()],
+        ];
+        let blended_list = file_readers::blend_lists_with_weights(&word_lists_by_file, &[3, 1]);
+
+        assert_eq!(
+            blended_list,
+            [
+                "a1", "a2", "a3", "b1", "a4", "a5", "a6", "b2", "a7", "a8", "b3"
+            ]
+            .to_vec()
+        );
+    }
+
     #[test]
     fn can_concatenate_multiple_lists() {
         let word_lists_by_file = vec![
@@ -42,4 +63,343 @@ mod list_reading_tests {
             ["one", "three", "five", "two", "four", "six", "eight", "ten"].to_vec()
         );
     }
+
+    use std::path::PathBuf;
+    #[test]
+    fn treats_a_dash_as_stdin() {
+        assert!(file_readers::is_stdin(&PathBuf::from("-")));
+        assert!(!file_readers::is_stdin(&PathBuf::from("words.txt")));
+        assert!(!file_readers::is_stdin(&PathBuf::from("./-")));
+    }
+
+    #[test]
+    fn can_read_a_word_list_from_stdin() {
+        let stdin = std::io::Cursor::new("zebra\napple\n".as_bytes().to_vec());
+        let word_lists_by_file = file_readers::read_word_lists_by_file_with_stdin(
+            &[PathBuf::from("-")],
+            stdin,
+            None,
+            None,
+            &tidy::decoding::ReadOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(word_lists_by_file, vec![vec!["zebra", "apple"]]);
+
+        // Stdin can only be read once
+        let stdin = std::io::Cursor::new(b"zebra\n".to_vec());
+        assert!(
+            file_readers::read_word_lists_by_file_with_stdin(
+                &[PathBuf::from("-"), PathBuf::from("-")],
+                stdin,
+                None,
+                None,
+                &tidy::decoding::ReadOptions::default(),
+            )
+            .is_err()
+        );
+    }
+
+    use std::fs::File;
+    use std::io::Write;
+    use tidy::compression::compress_for_path;
+    #[test]
+    fn can_read_compressed_word_lists() {
+        for extension in ["gz", "xz", "zst"] {
+            let path = std::env::temp_dir().join(format!(
+                "tidy-compression-test-{}.txt.{}",
+                std::process::id(),
+                extension
+            ));
+            let f = File::create(&path).unwrap();
+            let mut writer = compress_for_path(f, &path).unwrap();
+            writeln!(writer, "zebra\nabacus").unwrap();
+            writer.finish().unwrap();
+
+            let list = file_readers::make_vec_from_filenames(
+                std::slice::from_ref(&path),
+                false,
+                None,
+                None,
+                &Default::default(),
+            )
+            .unwrap();
+            assert_eq!(list, vec!["zebra".to_string(), "abacus".to_string()]);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    /// A writer that fails once it's been given a few bytes, like a nearly full disk
+    struct NearlyFullDisk {
+        space: usize,
+    }
+    impl Write for NearlyFullDisk {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.len() > self.space {
+                return Err(std::io::Error::other("disk is full"));
+            }
+            self.space -= buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn returns_errors_from_finishing_compressed_output() {
+        for extension in ["gz", "xz", "zst"] {
+            let path = std::path::PathBuf::from(format!("list.txt.{}", extension));
+            let mut writer = compress_for_path(NearlyFullDisk { space: 4 }, &path).unwrap();
+            // Compressors hold on to what's written, so the error only comes when
+            // the stream is finished
+            let _ = writer.write_all(b"zebra\nabacus\n");
+            assert!(writer.finish().is_err(), "{} output didn't fail", extension);
+        }
+    }
+
+    use tidy::decoding::{InputEncoding, ReadOptions, decode_lines};
+    #[test]
+    fn removes_byte_order_mark_and_reports_invalid_lines() {
+        let bytes = b"\xef\xbb\xbfcaf\xc3\xa9\r\nna\xefve\nzoo\n";
+        let (lines, report) = decode_lines(bytes, &ReadOptions::default());
+        assert_eq!(lines, vec!["café", "zoo"]);
+        assert_eq!(report.skipped_lines, vec![2]);
+
+        let lossy = ReadOptions {
+            lossy: true,
+            ..Default::default()
+        };
+        let (lines, report) = decode_lines(bytes, &lossy);
+        assert_eq!(lines, vec!["café", "na\u{FFFD}ve", "zoo"]);
+        assert_eq!(report.repaired_lines, vec![2]);
+    }
+
+    #[test]
+    fn can_decode_legacy_encodings() {
+        let latin_1 = ReadOptions {
+            encoding: InputEncoding::Latin1,
+            ..Default::default()
+        };
+        assert_eq!(decode_lines(b"caf\xe9\n", &latin_1).0, vec!["café"]);
+        let windows_1252 = ReadOptions {
+            encoding: InputEncoding::Windows1252,
+            ..Default::default()
+        };
+        assert_eq!(decode_lines(b"\x80uro\n", &windows_1252).0, vec!["€uro"]);
+        // UTF-16 is detected by its byte order mark
+        let (lines, report) =
+            decode_lines(b"\xff\xfec\x00a\x00t\x00\n\x00", &ReadOptions::default());
+        assert_eq!(lines, vec!["cat"]);
+        assert_eq!(report.encoding, InputEncoding::Utf16Le);
+    }
+
+    #[test]
+    fn only_counts_replacements_made_while_decoding_utf16() {
+        // Line 1 has a replacement character on purpose, while line 2 has an
+        // unpaired surrogate, which the decoder has to replace
+        let bytes = b"\xff\xfea\x00\xfd\xff\n\x00b\x00\x00\xd8\n\x00c\x00\n\x00";
+        let (lines, report) = decode_lines(bytes, &ReadOptions::default());
+        assert_eq!(lines, vec!["a\u{FFFD}", "c"]);
+        assert_eq!(report.skipped_lines, vec![2]);
+
+        let lossy = ReadOptions {
+            lossy: true,
+            ..Default::default()
+        };
+        let (lines, report) = decode_lines(bytes, &lossy);
+        assert_eq!(lines, vec!["a\u{FFFD}", "b\u{FFFD}", "c"]);
+        assert_eq!(report.repaired_lines, vec![2]);
+    }
+
+    #[test]
+    fn can_decode_utf16_in_small_reads() {
+        use std::io::BufReader;
+        use tidy::decoding::decode_numbered_lines;
+        let bytes: &[u8] = b"\xfe\xff\x00c\x00a\x00t\x00\r\x00\n\x00d\x00o\x00g";
+        // A buffer of one byte splits every character across reads
+        let reader = BufReader::with_capacity(1, bytes);
+        let (numbered_lines, report) =
+            decode_numbered_lines(reader, &ReadOptions::default()).unwrap();
+        assert_eq!(
+            numbered_lines,
+            vec![(1, "cat".to_string()), (2, "dog".to_string())]
+        );
+        assert_eq!(report.encoding, InputEncoding::Utf16Be);
+    }
+
+    use tidy::hunspell::{HunspellOptions, parse_affix_file};
+    fn make_lines(text: &str) -> Vec<String> {
+        text.lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn can_expand_hunspell_affix_rules() {
+        let affix_file = parse_affix_file(&make_lines(
+            "SET UTF-8\nNEEDAFFIX X\nPFX U Y 1\nPFX U 0 un .\nSFX S Y 2\nSFX S y ies [^aeiou]y\nSFX S 0 s [^y]\nSFX D N 1\nSFX D 0 ed [^e]",
+        ));
+        let dictionary = make_lines("3\ncarry/S\nlock/UDS\nfoot/X\tpo:noun");
+        let mut words = affix_file.expand_dictionary(&dictionary, &HunspellOptions::default());
+        words.sort();
+        // D doesn't allow cross products, so there's no "unlocked"
+        assert_eq!(
+            words,
+            vec![
+                "carries", "carry", "lock", "locked", "locks", "unlock", "unlocks"
+            ]
+        );
+
+        // Only the first line is the number of entries, so later numbers are words
+        let dictionary_with_numbers = make_lines("3\n42\nand\\/or/S");
+        assert_eq!(
+            affix_file.expand_dictionary(&dictionary_with_numbers, &HunspellOptions::default()),
+            vec!["42", "and/or", "and/ors"]
+        );
+
+        let stems_only = HunspellOptions {
+            stems_only: true,
+            flags: None,
+        };
+        assert_eq!(
+            affix_file.expand_dictionary(&dictionary, &stems_only),
+            vec!["carry", "lock", "foot"]
+        );
+
+        let only_plurals = HunspellOptions {
+            stems_only: false,
+            flags: Some(vec!["S".to_string()]),
+        };
+        assert_eq!(
+            affix_file.expand_dictionary(&dictionary, &only_plurals),
+            vec!["carry", "carries", "lock", "locks"]
+        );
+    }
+
+    #[test]
+    fn can_read_long_and_aliased_hunspell_flags() {
+        let affix_file = parse_affix_file(&make_lines(
+            "FLAG long\nAF 2\nAF PlPa\nAF Pl\nSFX Pl Y 1\nSFX Pl 0 s .\nSFX Pa Y 1\nSFX Pa 0 ed .",
+        ));
+        let dictionary = make_lines("2\nwalk/1\ncat/2");
+        assert_eq!(
+            affix_file.expand_dictionary(&dictionary, &HunspellOptions::default()),
+            vec!["walk", "walks", "walked", "cat", "cats"]
+        );
+    }
+
+    #[test]
+    fn can_read_hunspell_dictionary_in_declared_encoding() {
+        let directory = std::env::temp_dir();
+        let name = format!("tidy-hunspell-test-{}", std::process::id());
+        let dictionary_path = directory.join(format!("{}.dic", name));
+        let affix_path = directory.join(format!("{}.aff", name));
+        // A rule adding "és" (in Latin-1), as well as one adding "s"
+        std::fs::write(
+            &affix_path,
+            b"SET ISO8859-1\nSFX S Y 1\nSFX S 0 s .\nSFX E Y 1\nSFX E 0 \xe9s .\n",
+        )
+        .unwrap();
+        // "café" and "pan" in Latin-1
+        std::fs::write(&dictionary_path, b"2\ncaf\xe9/S\npan/E\n").unwrap();
+
+        let lists = file_readers::read_hunspell_dictionaries_by_file(
+            std::slice::from_ref(&dictionary_path),
+            &HunspellOptions::default(),
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(lists, vec![vec!["café", "cafés", "pan", "panés"]]);
+        std::fs::remove_file(dictionary_path).unwrap();
+        std::fs::remove_file(affix_path).unwrap();
+    }
+
+    #[test]
+    fn ignores_pgp_armor_and_comments() {
+        let path = std::env::temp_dir().join(format!("tidy-pgp-test-{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\n# A comment\n11111\tabacus\n- -dash\n-----BEGIN PGP SIGNATURE-----\n\niQIzBAEBCAAdFiEE\n-----END PGP SIGNATURE-----\n",
+        )
+        .unwrap();
+        let read_options = ReadOptions {
+            comment_prefix: Some("#".to_string()),
+            ..Default::default()
+        };
+        let list = file_readers::make_vec_from_filenames(
+            std::slice::from_ref(&path),
+            false,
+            None,
+            None,
+            &read_options,
+        )
+        .unwrap();
+        assert_eq!(list, vec!["11111\tabacus", "-dash"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn can_skip_rows_of_a_pgp_signed_list() {
+        let path = std::env::temp_dir().join(format!(
+            "tidy-pgp-skip-rows-test-{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            "-----BEGIN PGP SIGNED MESSAGE-----\nHash: SHA256\n\nabacus\nabbey\nzebra\n-----BEGIN PGP SIGNATURE-----\n\niQIzBAEBCAAdFiEE\n-----END PGP SIGNATURE-----\n",
+        )
+        .unwrap();
+        let list = file_readers::make_vec_from_filenames(
+            std::slice::from_ref(&path),
+            false,
+            Some(3),
+            Some(4),
+            &ReadOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(list, vec!["abacus", "abbey", "zebra"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn can_read_groups_of_homophones_and_report_malformed_lines() {
+        let path =
+            std::env::temp_dir().join(format!("tidy-homophones-test-{}.txt", std::process::id()));
+        std::fs::write(&path, "sun,son\n\nto, too ,two\n").unwrap();
+        let homophones = file_readers::read_homophones_list_from_filenames(
+            std::slice::from_ref(&path),
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            homophones,
+            vec![vec!["sun", "son"], vec!["to", "too", "two"]]
+        );
+
+        std::fs::write(&path, "sun,son\n\nlonely\n").unwrap();
+        let error = file_readers::read_homophones_list_from_filenames(
+            std::slice::from_ref(&path),
+            &Default::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            TidyError::MalformedLine { line_number: 3, ref line, .. } if line == "lonely"
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reports_which_file_could_not_be_read() {
+        let path = std::path::PathBuf::from("tests/test-files/no-such-list.txt");
+        let error = file_readers::make_vec_from_filenames(
+            std::slice::from_ref(&path),
+            false,
+            None,
+            None,
+            &Default::default(),
+        )
+        .unwrap_err();
+        assert!(
+            matches!(error, TidyError::Read { path: ref error_path, .. } if *error_path == path)
+        );
+    }
 }
@@ -0,0 +1,46 @@
+mod manifest_tests {
+    use std::io::Write;
+    use tidy::file_writer::write_atomically;
+    use tidy::manifest::*;
+
+    #[test]
+    fn can_hash_a_file() {
+        let path = std::env::temp_dir().join(format!("tidy-hash-test-{}.txt", std::process::id()));
+        std::fs::write(&path, "abc").unwrap();
+        let hashed_file = hash_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            hashed_file.sha256,
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string())
+        );
+    }
+
+    #[test]
+    fn does_not_hash_stdin() {
+        let hashed_file = hash_file(std::path::Path::new("-")).unwrap();
+        assert_eq!(hashed_file.sha256, None);
+    }
+
+    #[test]
+    fn atomic_writes_leave_no_temporary_file_behind() {
+        let directory =
+            std::env::temp_dir().join(format!("tidy-atomic-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("new_list.txt");
+        std::fs::write(&path, "old list\n").unwrap();
+
+        // A failed write leaves the old file as it was
+        let result = write_atomically(&path, |mut f| {
+            writeln!(f, "half a")?;
+            Err(std::io::Error::other("stopped partway through"))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old list\n");
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+
+        write_atomically(&path, |mut f| writeln!(f, "new list")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new list\n");
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -18,4 +18,12 @@ mod parse_tests {
     fn panics_when_too_many_exponents_inputted_to_print_rand() {
         assert!(eval_list_length("2**4**3").is_err());
     }
+
+    #[test]
+    fn can_parse_products_of_dice_sides() {
+        assert_eq!(eval_list_length("20*12*10*8").unwrap(), 19_200);
+        assert_eq!(eval_list_length("6**4*4").unwrap(), 5_184);
+        assert!(eval_list_length("20*").is_err());
+        assert!(eval_list_length("20*twelve").is_err());
+    }
 }
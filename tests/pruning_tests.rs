@@ -4,7 +4,7 @@ mod pruning_tests {
 
     #[test]
     fn can_resist_pruning_a_list_that_is_already_uniquely_decodable() {
-        let list: Vec<String> = vec!["101", "00", "0001", "1"]
+        let list: Vec<String> = ["101", "00", "0001", "1"]
             .iter()
             .map(|w| w.to_string())
             .collect();
@@ -13,13 +13,13 @@ mod pruning_tests {
             should_schlinkert_prune: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         assert_eq!(list, new_list);
     }
 
     #[test]
     fn can_run_schlinkert_prune_on_reversed_list_if_it_saves_more_words() {
-        let list: Vec<String> = vec![
+        let list: Vec<String> = [
             "news",
             "paper",
             "newspaper",
@@ -35,11 +35,11 @@ mod pruning_tests {
         .collect();
 
         let this_tidy_request = TidyRequest {
-            list: list,
+            list,
             should_schlinkert_prune: true,
             ..Default::default()
         };
-        let new_list = tidy_list(this_tidy_request);
+        let new_list = tidy_list(this_tidy_request).unwrap();
         // If Schlinkert prune was done in forwards, only
         // 5 words would be saved. But if we Schlinkert
         // prune the reversed list, we save 8 words.
@@ -0,0 +1,92 @@
+mod recipe_tests {
+    use serde_json::json;
+    use std::path::PathBuf;
+    use std::process::{Command, Output};
+    use tidy::recipe::*;
+
+    /// Run Tidy itself (so that recipes are checked against its real options)
+    fn run_tidy(recipe_path: &PathBuf, arguments: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_tidy"))
+            .arg("--recipe")
+            .arg(recipe_path)
+            .args(arguments)
+            .output()
+            .unwrap()
+    }
+
+    fn make_test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("tidy-recipe-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn command_line_overrides_recipe() {
+        let directory = make_test_directory("override");
+        std::fs::write(
+            directory.join("words.txt"),
+            "Zebra\nox\napple\n007 secret\nbanana\n",
+        )
+        .unwrap();
+        // Paths in a recipe are relative to the recipe's directory
+        let recipe_path = directory.join("recipe.toml");
+        std::fs::write(
+            &recipe_path,
+            "inputs = [\"words.txt\"]\noutput = \"new_list.txt\"\nlowercase = true\nminimum-word-length = 3\n",
+        )
+        .unwrap();
+        let saved_recipe_path = directory.join("saved.toml");
+        let output = run_tidy(
+            &recipe_path,
+            &[
+                "--save-recipe",
+                saved_recipe_path.to_str().unwrap(),
+                "-m",
+                "5",
+                "--comment-prefix",
+                "007",
+            ],
+        );
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(
+            std::fs::read_to_string(directory.join("new_list.txt")).unwrap(),
+            "apple\nbanana\nzebra\n"
+        );
+
+        // The saved recipe has the options of both, each with its type
+        let saved_recipe = read_recipe(&saved_recipe_path).unwrap();
+        assert_eq!(saved_recipe["minimum-word-length"], json!(5));
+        assert_eq!(saved_recipe["comment-prefix"], json!("007"));
+        assert_eq!(saved_recipe["lowercase"], json!(true));
+        assert_eq!(saved_recipe["inputs"], json!(["words.txt"]));
+        assert_eq!(saved_recipe["output"], json!("new_list.txt"));
+
+        // Saving a recipe over an existing file needs --force
+        let save_over_recipe = ["--dry-run", "--save-recipe", recipe_path.to_str().unwrap()];
+        let output = run_tidy(&saved_recipe_path, &save_over_recipe);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+        let output = run_tidy(
+            &saved_recipe_path,
+            &[&save_over_recipe[..], &["--force"]].concat(),
+        );
+        assert!(output.status.success(), "{:?}", output);
+        let overwritten_recipe = read_recipe(&recipe_path).unwrap();
+        assert_eq!(overwritten_recipe["dry-run"], json!(true));
+        assert_eq!(overwritten_recipe["inputs"], json!(["words.txt"]));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_options_in_recipes() {
+        let directory = make_test_directory("unknown");
+        let recipe_path = directory.join("recipe.json");
+        std::fs::write(&recipe_path, r#"{"lowercase": true, "uppercase": true}"#).unwrap();
+        let output = run_tidy(&recipe_path, &[]);
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("--uppercase"));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
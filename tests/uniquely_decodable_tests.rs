@@ -3,7 +3,7 @@ mod uniquely_decodable_tests {
 
     #[test]
     fn can_determine_a_list_with_prefix_words_is_not_uniquely_decodable() {
-        let list: Vec<String> = vec!["news", "newspaper", "paper", "elephant"]
+        let list: Vec<String> = ["news", "newspaper", "paper", "elephant"]
             .iter()
             .map(|x| x.to_string())
             .collect();
@@ -22,7 +22,7 @@ mod uniquely_decodable_tests {
 
     #[test]
     fn can_determine_that_a_list_is_uniquely_decodable() {
-        let list: Vec<String> = vec![
+        let list: Vec<String> = [
             "excursion",
             "friday",
             "gyration",
@@ -44,7 +44,7 @@ mod uniquely_decodable_tests {
 
     #[test]
     fn can_determine_binary_code_with_a_suffix_code_is_not_uniquely_decodable() {
-        let list: Vec<String> = vec!["02", "12", "120", "20", "21"]
+        let list: Vec<String> = ["02", "12", "120", "20", "21"]
             .iter()
             .map(|w| w.to_string())
             .collect();
@@ -53,26 +53,26 @@ mod uniquely_decodable_tests {
 
     #[test]
     fn given_a_series_of_binary_codes_can_determine_which_are_uniquely_decodable() {
-        let list: Vec<String> = vec!["0", "10", "110", "111"]
+        let list: Vec<String> = ["0", "10", "110", "111"]
             .iter()
             .map(|w| w.to_string())
             .collect();
         assert!(is_uniquely_decodable(&list));
 
-        let list: Vec<String> = vec!["0", "10", "010", "101"]
+        let list: Vec<String> = ["0", "10", "010", "101"]
             .iter()
             .map(|w| w.to_string())
             .collect();
         assert!(!is_uniquely_decodable(&list));
 
-        let list: Vec<String> = vec!["0", "01", "011", "0111"]
+        let list: Vec<String> = ["0", "01", "011", "0111"]
             .iter()
             .map(|w| w.to_string())
             .collect();
         assert!(is_uniquely_decodable(&list));
 
         // '0, 1, 00, 11' is not an uniquely decodable code
-        let list: Vec<String> = vec!["0", "1", "00", "11"]
+        let list: Vec<String> = ["0", "1", "00", "11"]
             .iter()
             .map(|w| w.to_string())
             .collect();
@@ -81,7 +81,7 @@ mod uniquely_decodable_tests {
 
     #[test]
     fn knows_that_a_fixed_length_code_is_uniquely_decodable() {
-        let list: Vec<String> = vec![
+        let list: Vec<String> = [
             "buoy", "cote", "dads", "duel", "gale", "life", "lurk", "peer", "rain", "tong",
         ]
         .iter()